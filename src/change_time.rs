@@ -0,0 +1,62 @@
+use chrono::NaiveDateTime;
+use std::collections::HashSet;
+use std::io;
+
+use crate::entry::Entry;
+use crate::format::Formatter;
+use crate::prompt;
+use crate::store::JournalStore;
+
+type Result<T> = std::result::Result<T, Box<dyn std::error::Error>>;
+
+/// Move the `entries` the user confirms one by one, or all of them with
+/// `yes`, to `time`. With `dry_run`, the entries that would move are shown
+/// instead.
+pub fn change_time(
+    entries: &[&Entry],
+    time: NaiveDateTime,
+    store: &mut dyn JournalStore,
+    formatter: &dyn Formatter,
+    yes: bool,
+    dry_run: bool,
+) -> Result<()> {
+    if entries.is_empty() {
+        eprintln!("[No entries to change the time of]");
+        return Ok(());
+    }
+    let when = time.format("%F %R");
+    if dry_run {
+        print!("{}", formatter.format_entries(entries));
+        eprintln!(
+            "[{} entries would be moved to {when}, nothing changed]",
+            entries.len()
+        );
+        return Ok(());
+    }
+    let selected = match yes {
+        true => entries.to_vec(),
+        false => prompt::confirm_each(
+            entries,
+            &format!("Change the time of this entry to {when}?"),
+            formatter,
+            &mut io::stdin().lock(),
+        )?,
+    };
+    let selected = selected.into_iter().cloned().collect::<Vec<_>>();
+    let mut changed = 0;
+    if !selected.is_empty() {
+        // the day moved to is rewritten too, so its entries must be loaded
+        let days = selected
+            .iter()
+            .map(|entry| entry.time.date())
+            .chain([time.date()])
+            .collect::<HashSet<_>>();
+        let mut journal = store.load(&|date| days.contains(&date))?;
+        changed = journal.change_time(&selected, time);
+        if changed > 0 {
+            store.replace(&journal)?;
+        }
+    }
+    eprintln!("[{changed} entries moved to {when}]");
+    Ok(())
+}
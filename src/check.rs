@@ -0,0 +1,28 @@
+use std::path::Path;
+
+use crate::error::{JrnlError, JrnlErrorKind};
+use crate::settings::Settings;
+use crate::store;
+
+type Result<T> = std::result::Result<T, Box<dyn std::error::Error>>;
+
+/// List everything that looks wrong in how the journal is written, failing if
+/// anything does. Nothing is changed.
+pub fn check(settings: &Settings, journal_name: &str, journal_file: &Path) -> Result<()> {
+    let problems = store::open(settings, journal_name, journal_file)?.check()?;
+    for problem in &problems {
+        eprintln!("{problem}\n");
+    }
+    match problems.len() {
+        0 => {
+            eprintln!("[No problems found in {journal_name} journal]");
+            Ok(())
+        }
+        1 => Err(JrnlError::new(JrnlErrorKind::InvalidJournal)
+            .context(format!("1 problem found in {journal_name} journal"))
+            .into()),
+        n => Err(JrnlError::new(JrnlErrorKind::InvalidJournal)
+            .context(format!("{n} problems found in {journal_name} journal"))
+            .into()),
+    }
+}
@@ -2,10 +2,11 @@ use clap::{ArgAction, Args, Parser, Subcommand, ValueEnum};
 use config::{ConfigError, Source, Value, ValueKind};
 use std::collections::HashMap;
 
+use crate::error::{self, JrnlError, JrnlErrorKind};
 use crate::settings::DisplayConfig;
 
 #[derive(Debug, Parser, Clone)]
-#[command(author, no_binary_name=false, version, about, long_about = None, bin_name="jrnl", disable_help_subcommand=true)]
+#[command(author, no_binary_name=false, version, about, long_about = None, bin_name="jrnl", disable_help_subcommand=true, after_help=error::exit_codes_help())]
 pub struct Cli {
     #[command(subcommand)]
     pub command: Option<Commands>,
@@ -117,6 +118,7 @@ pub struct SearchOptions {
         long,
         help = "If passed a string, will exclude entries with that tag. Can also be used before --starred or '--tagged' flags to invert the matching criteria of those flags",
         value_name = "TAG/FLAG",
+        allow_hyphen_values = true,
         required = false
     )]
     pub not: Option<String>,
@@ -137,10 +139,27 @@ pub struct SearchOptions {
     )]
     pub delete: Option<bool>,
 
+    #[arg(
+        long,
+        action = ArgAction::SetTrue,
+        help = "Don't ask for confirmation with --delete or --change-time",
+        required = false
+    )]
+    pub yes: Option<bool>,
+
+    #[arg(
+        long,
+        action = ArgAction::SetTrue,
+        help = "Show the entries --delete or --change-time would change, changing nothing",
+        required = false
+    )]
+    pub dry_run: Option<bool>,
+
     #[arg(
         long,
         value_name = "DATE",
-        default_value = "now",
+        num_args = 0..=1,
+        default_missing_value = "now",
         required = false,
         help = "Change timestamp for selected entries"
     )]
@@ -165,6 +184,7 @@ pub struct SearchOptions {
 
     #[arg(
         long,
+        action = ArgAction::SetTrue,
         required = false,
         help = "Alias for '--format tags'. Returns a list of all tags and number of occurrences"
     )]
@@ -173,12 +193,54 @@ pub struct SearchOptions {
     #[arg(
         long,
         required = false,
-        action,
+        action = ArgAction::SetTrue,
         help = "Alias for '--format short'. Show only titles or line containing the search tags"
     )]
     pub short: Option<bool>,
 }
 
+impl SearchOptions {
+    /// The display format requested with `--format`, `--tags` or `--short`.
+    pub fn display_format(&self) -> Option<DisplayConfig> {
+        match (self.format, self.tags, self.short) {
+            (Some(format), _, _) => Some(format),
+            (None, Some(true), _) => Some(DisplayConfig::Tags),
+            (None, _, Some(true)) => Some(DisplayConfig::Short),
+            _ => None,
+        }
+    }
+
+    /// Whether any search, display or action flag was given.
+    pub fn is_empty(&self) -> bool {
+        let flags = [
+            self.today_in_history,
+            self.and,
+            self.starred,
+            self.tagged,
+            self.edit,
+            self.delete,
+            self.tags,
+            self.short,
+        ];
+        let values = [
+            &self.on,
+            &self.month,
+            &self.day,
+            &self.year,
+            &self.from,
+            &self.to,
+            &self.contains,
+            &self.not,
+            &self.change_time,
+            &self.file,
+        ];
+        !flags.contains(&Some(true))
+            && values.iter().all(|value| value.is_none())
+            && self.n.is_none()
+            && self.format.is_none()
+    }
+}
+
 #[derive(Debug, Args, Clone)]
 pub struct FormatArgs {
     format: DisplayConfig,
@@ -207,10 +269,28 @@ pub enum Commands {
         long_flag = "decrypt",
         about = "Decrypt selected journal and store it in plain text"
     )]
-    Decrypt,
+    Decrypt(DecryptArgs),
+
+    #[command(
+        long_flag = "forget-password",
+        about = "Forget the saved password of selected journal"
+    )]
+    ForgetPassword,
 
     #[command(long_flag = "import", about = "Import entries from another journal")]
     Import(ImportArgs),
+
+    #[command(
+        long_flag = "migrate",
+        about = "Copy the selected journal's entries to another journal path"
+    )]
+    Migrate(MigrateArgs),
+
+    #[command(
+        long_flag = "check",
+        about = "List the problems in how the selected journal is written, changing nothing"
+    )]
+    Check,
 }
 
 #[derive(Debug, Clone, Args)]
@@ -225,17 +305,38 @@ pub enum ListFormat {
     Yaml,
 }
 
+#[derive(Debug, Args, Clone)]
+pub struct DecryptArgs {
+    /// Write the plain text here instead of replacing the journal
+    #[arg(value_name = "FILENAME")]
+    pub(crate) file: Option<String>,
+}
+
 #[derive(Debug, Args, Clone)]
 pub struct ImportArgs {
     #[arg(long, value_name = "FILENAME", default_value = "stdin")]
-    file: String,
+    pub(crate) file: String,
     #[arg(long, default_value = "jrnl")]
-    format: ImportFormat,
+    pub(crate) format: ImportFormat,
+}
+
+#[derive(Debug, Args, Clone)]
+pub struct MigrateArgs {
+    /// A text file, a folder (ending in '/') or an sqlite:// database
+    #[arg(value_name = "PATH")]
+    pub(crate) to: String,
 }
 
 #[derive(Debug, Clone, ValueEnum)]
 pub enum ImportFormat {
     Jrnl,
+    Json,
+    #[value(name = "dayone")]
+    DayOne,
+    #[value(alias = "md")]
+    Markdown,
+    #[value(alias = "yml")]
+    Yaml,
 }
 
 impl Source for Cli {
@@ -245,88 +346,66 @@ impl Source for Cli {
 
     fn collect(&self) -> Result<HashMap<String, Value>, ConfigError> {
         let mut map = HashMap::new();
+        let cli = Some(&"cli".to_owned());
         for chunk in self.config_override.chunks_exact(2) {
-            match chunk {
-                [k, v] => match k.as_str() {
-                    k if k.starts_with("color.") => {
-                        let key = "color".to_string();
-                        let color_section = k.strip_prefix("color.").unwrap().to_string();
-                        let value = Value::new(None, ValueKind::String(v.to_string()));
-                        map.entry(key)
-                            .and_modify(|table| match table {
-                                Value {
-                                    kind: ValueKind::Table(tab),
-                                    ..
-                                } => {
-                                    tab.insert(color_section.clone(), value.clone());
-                                }
-                                _ => panic!("nope"),
-                            })
-                            .or_insert_with(|| {
-                                let mut map = HashMap::new();
-                                map.insert(color_section.clone(), value.clone());
-                                let vk = ValueKind::Table(map);
-                                Value::new(Some(&"cli".to_owned()), vk)
-                            });
-                    }
-                    k if k.starts_with("journals.") => {
-                        let key = "journals".to_string();
-                        let journal_name = k.strip_prefix("journals.").unwrap().to_string();
-                        let path = Value::new(None, ValueKind::String(v.to_string()));
-                        map.entry(key)
-                            .and_modify(|table| match table {
-                                Value {
-                                    kind: ValueKind::Table(tab),
-                                    ..
-                                } => {
-                                    tab.insert(journal_name.clone(), path.clone());
-                                }
-                                _ => panic!("nope"),
-                            })
-                            .or_insert_with(|| {
-                                let mut map = HashMap::new();
-                                map.insert(journal_name.clone(), path.clone());
-                                let vk = ValueKind::Table(map);
-                                Value::new(Some(&"cli".to_owned()), vk)
-                            });
-                    }
-                    "encrypt" | "highlight" => match v.to_lowercase().as_str() {
-                        "false" | "0" => {
-                            map.insert(
-                                k.to_string(),
-                                Value::new(Some(&"cli".to_owned()), ValueKind::Boolean(false)),
-                            );
-                        }
-                        "true" | "1" => {
-                            map.insert(
-                                k.to_string(),
-                                Value::new(Some(&"cli".to_owned()), ValueKind::Boolean(true)),
-                            );
-                        }
-                        _ => panic!("asdf"),
-                    },
-                    "default_minute" | "default_hour" | "linewrap" => {
-                        map.insert(
-                            k.to_string(),
-                            Value::new(
-                                Some(&"cli".to_owned()),
-                                ValueKind::I64(v.parse::<i64>().expect("not a number")),
-                            ),
-                        );
-                    }
-                    _ => {
-                        map.insert(
-                            k.to_string(),
-                            Value::new(
-                                Some(&"cli".to_owned()),
-                                ValueKind::String(v.clone().to_string()),
-                            ),
-                        );
-                    }
+            let [k, v] = chunk else {
+                unreachable!("chunks have two elements")
+            };
+            let value = match k.as_str() {
+                k if k.starts_with("color.") => {
+                    let color_section = k.strip_prefix("color.").unwrap_or_default();
+                    insert_into_table(&mut map, "color", color_section, v)?;
+                    continue;
+                }
+                k if k.starts_with("journals.") => {
+                    let journal_name = k.strip_prefix("journals.").unwrap_or_default();
+                    insert_into_table(&mut map, "journals", journal_name, v)?;
+                    continue;
+                }
+                "encrypt" | "highlight" => match v.to_lowercase().as_str() {
+                    "false" | "0" => ValueKind::Boolean(false),
+                    "true" | "1" => ValueKind::Boolean(true),
+                    _ => Err(override_error(k, v, "expected true or false"))?,
+                },
+                "default_minute" | "default_hour" | "linewrap" => match v.parse::<i64>() {
+                    Ok(number) => ValueKind::I64(number),
+                    Err(_) => Err(override_error(k, v, "expected a number"))?,
                 },
-                _ => return Err(ConfigError::Message("asdfasdf".to_string())),
-            }
+                _ => ValueKind::String(v.to_string()),
+            };
+            map.insert(k.to_string(), Value::new(cli, value));
         }
         Ok(map)
     }
 }
+
+/// Set `field` of the table at `key` in `map` to the string `value`, creating
+/// the table if needed.
+fn insert_into_table(
+    map: &mut HashMap<String, Value>,
+    key: &str,
+    field: &str,
+    value: &str,
+) -> Result<(), ConfigError> {
+    let table = map
+        .entry(key.to_owned())
+        .or_insert_with(|| Value::new(Some(&"cli".to_owned()), ValueKind::Table(HashMap::new())));
+    match &mut table.kind {
+        ValueKind::Table(table) => {
+            let value = Value::new(None, ValueKind::String(value.to_owned()));
+            table.insert(field.to_owned(), value);
+            Ok(())
+        }
+        _ => Err(override_error(
+            &format!("{key}.{field}"),
+            value,
+            &format!("'{key}' is already overridden with a single value"),
+        )),
+    }
+}
+
+fn override_error(key: &str, value: &str, problem: &str) -> ConfigError {
+    let err = JrnlError::new(JrnlErrorKind::InvalidConfigOverride)
+        .context(format!("can't set '{key}' to '{value}', {problem}"));
+    ConfigError::Foreign(Box::new(err))
+}
@@ -0,0 +1,139 @@
+use chrono::{Local, NaiveDateTime, NaiveTime, Timelike};
+use std::fs;
+use std::path::Path;
+
+use crate::datetime;
+use crate::editor;
+use crate::entry::Entry;
+use crate::settings::{Settings, TemplateConfig};
+use crate::store;
+
+type Result<T> = std::result::Result<T, Box<dyn std::error::Error>>;
+
+/// Compose a new entry from the words given on the command line and append it
+/// to the journal.
+pub fn compose(
+    words: &[String],
+    settings: &Settings,
+    journal_name: &str,
+    journal_file: &Path,
+) -> Result<()> {
+    let raw = words.join(" ");
+    let entry = build_entry(&raw, settings, journal_name, Local::now().naive_local())?;
+    write_entry(&entry, settings, journal_name, journal_file)
+}
+
+/// Compose a new entry in the configured editor, seeded from the journal's
+/// template, and append it to the journal.
+pub fn compose_in_editor(
+    settings: &Settings,
+    journal_name: &str,
+    journal_file: &Path,
+) -> Result<()> {
+    let template = match settings.template(journal_name)? {
+        TemplateConfig::Path(path) => fs::read_to_string(&path)
+            .map_err(|err| format!("failed to read template '{path}': {err}"))?,
+        TemplateConfig::Empty(_) => String::new(),
+    };
+    let editor = settings
+        .editor(journal_name)
+        .map_err(|_| "no editor configured; set 'editor' in your config file")?;
+    let buffer = editor::edit(&editor, &template)?;
+    if buffer.trim().is_empty() || buffer.trim() == template.trim() {
+        eprintln!("[Nothing saved to file]");
+        return Ok(());
+    }
+
+    let lines = buffer.trim().lines().collect::<Vec<_>>();
+    let timeformat = settings.timeformat(journal_name)?;
+    let entry = match Entry::parse(&lines, &timeformat) {
+        Ok(entry) => entry,
+        Err(_) => build_entry(&buffer, settings, journal_name, Local::now().naive_local())?,
+    };
+    write_entry(&entry, settings, journal_name, journal_file)
+}
+
+pub fn write_entry(
+    entry: &Entry,
+    settings: &Settings,
+    journal_name: &str,
+    journal_file: &Path,
+) -> Result<()> {
+    store::open(settings, journal_name, journal_file)?.append(entry)?;
+    eprintln!("[Entry added to {journal_name} journal]");
+    Ok(())
+}
+
+/// Turn raw entry text into an `Entry`, honoring a leading time prefix such as
+/// `yesterday 5pm: ...` and a `*` marking the entry as starred.
+pub fn build_entry(
+    raw: &str,
+    settings: &Settings,
+    journal_name: &str,
+    now: NaiveDateTime,
+) -> Result<Entry> {
+    let default_time = default_time(settings, journal_name)?;
+    let now = now.with_nanosecond(0).unwrap_or(now);
+    let raw = raw.trim();
+
+    let first_line = raw.lines().next().unwrap_or_default();
+    let prefix = first_line.find(": ").and_then(|colon| {
+        let when = raw[..colon].trim();
+        let starred = when.ends_with('*');
+        datetime::parse(when.trim_end_matches('*'), now, default_time)
+            .map(|time| (time, starred, raw[colon + 1..].trim()))
+    });
+
+    let entry = match prefix {
+        Some((time, true, text)) if !text.starts_with('*') => {
+            Entry::compose(time, &format!("*{text}"))
+        }
+        Some((time, _, text)) => Entry::compose(time, text),
+        None => Entry::compose(now, raw),
+    };
+    Ok(entry)
+}
+
+pub fn default_time(settings: &Settings, journal_name: &str) -> Result<NaiveTime> {
+    let hour = settings.default_hour(journal_name)?;
+    let minute = settings.default_minute(journal_name)?;
+    NaiveTime::from_hms_opt(hour.try_into()?, minute.try_into()?, 0)
+        .ok_or_else(|| format!("invalid default time {hour}:{minute}").into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::NaiveDate;
+
+    fn now() -> NaiveDateTime {
+        NaiveDate::from_ymd_opt(2023, 1, 12)
+            .unwrap()
+            .and_hms_opt(14, 30, 15)
+            .unwrap()
+    }
+
+    #[test]
+    fn test_build_entry() {
+        let settings = Settings::default().with_journal("default", "/tmp/journal.txt");
+        let entry = build_entry("today was good. @work", &settings, "default", now()).unwrap();
+        assert_eq!(entry.time, now());
+        assert_eq!(
+            entry.format("%F %R"),
+            "[2023-01-12 14:30] today was good.\n@work\n"
+        );
+
+        let entry = build_entry("yesterday 5pm: Dinner. Pasta", &settings, "default", now());
+        assert_eq!(
+            entry.unwrap().format("%F %R"),
+            "[2023-01-11 17:00] Dinner.\nPasta\n"
+        );
+
+        let entry = build_entry("yesterday*: Big day", &settings, "default", now()).unwrap();
+        assert_eq!(entry.format("%F %R"), "[2023-01-11 09:00] *Big day\n");
+
+        // a colon that doesn't follow a date is part of the text
+        let entry = build_entry("Note: buy milk", &settings, "default", now()).unwrap();
+        assert_eq!(entry.format("%F %R"), "[2023-01-12 14:30] Note: buy milk\n");
+    }
+}
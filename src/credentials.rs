@@ -0,0 +1,192 @@
+use directories::ProjectDirs;
+use indexmap::IndexMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::crypto;
+use crate::journal;
+use crate::settings::{PasswordStoreConfig, Settings};
+
+type Result<T> = std::result::Result<T, Box<dyn std::error::Error>>;
+
+/// Somewhere to remember the passwords of encrypted journals, keyed by
+/// journal name.
+pub trait CredentialStore {
+    fn get(&self, journal_name: &str) -> Result<Option<String>>;
+    fn set(&self, journal_name: &str, password: &str) -> Result<()>;
+    /// Forget the password, returning whether there was one.
+    fn delete(&self, journal_name: &str) -> Result<bool>;
+}
+
+/// The store configured for the journal, if passwords are remembered at all.
+pub fn store(settings: &Settings, journal_name: &str) -> Result<Option<Box<dyn CredentialStore>>> {
+    Ok(match settings.password_store(journal_name)? {
+        PasswordStoreConfig::Prompt => None,
+        PasswordStoreConfig::File => Some(Box::new(FileStore::new(default_path()?))),
+        #[cfg(feature = "secret-service")]
+        PasswordStoreConfig::SecretService => Some(Box::new(SecretServiceStore)),
+        #[cfg(not(feature = "secret-service"))]
+        PasswordStoreConfig::SecretService => Err(
+            "jrnl was built without Secret Service support; rebuild with --features secret-service",
+        )?,
+    })
+}
+
+/// Ask for a new password for the journal, remembering it in `store`.
+pub fn new_password(journal_name: &str, store: Option<&dyn CredentialStore>) -> Result<String> {
+    let password = crypto::prompt_new_password()?;
+    if let Some(store) = store {
+        store.set(journal_name, &password)?;
+    }
+    Ok(password)
+}
+
+/// Forget the journal's saved password.
+pub fn forget(settings: &Settings, journal_name: &str) -> Result<()> {
+    let forgotten = match store(settings, journal_name)? {
+        Some(store) => store.delete(journal_name)?,
+        None => false,
+    };
+    match forgotten {
+        true => eprintln!("[Password for journal '{journal_name}' forgotten]"),
+        false => eprintln!("[No password saved for journal '{journal_name}']"),
+    }
+    Ok(())
+}
+
+fn default_path() -> Result<PathBuf> {
+    let dirs = ProjectDirs::from("", "", "jrnl").ok_or("no home directory found")?;
+    Ok(dirs.data_local_dir().join("passwords.yaml"))
+}
+
+/// Passwords in a YAML file readable only by the user, for machines without a
+/// keyring daemon.
+pub struct FileStore {
+    path: PathBuf,
+}
+
+impl FileStore {
+    pub fn new(path: PathBuf) -> Self {
+        Self { path }
+    }
+
+    fn load(&self) -> Result<IndexMap<String, String>> {
+        match fs::read_to_string(&self.path) {
+            Ok(yaml) if yaml.trim().is_empty() => Ok(IndexMap::new()),
+            Ok(yaml) => Ok(serde_yml::from_str(&yaml)?),
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(IndexMap::new()),
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    fn save(&self, passwords: &IndexMap<String, String>) -> Result<()> {
+        if let Some(parent) = self.path.parent() {
+            fs::create_dir_all(parent)?;
+            restrict(parent, 0o700)?;
+        }
+        journal::write_atomic(&self.path, serde_yml::to_string(passwords)?.as_bytes())?;
+        restrict(&self.path, 0o600)
+    }
+}
+
+impl CredentialStore for FileStore {
+    fn get(&self, journal_name: &str) -> Result<Option<String>> {
+        Ok(self.load()?.get(journal_name).cloned())
+    }
+
+    fn set(&self, journal_name: &str, password: &str) -> Result<()> {
+        let mut passwords = self.load()?;
+        passwords.insert(journal_name.to_owned(), password.to_owned());
+        self.save(&passwords)
+    }
+
+    fn delete(&self, journal_name: &str) -> Result<bool> {
+        let mut passwords = self.load()?;
+        if passwords.shift_remove(journal_name).is_none() {
+            return Ok(false);
+        }
+        self.save(&passwords)?;
+        Ok(true)
+    }
+}
+
+#[cfg(unix)]
+fn restrict(path: &Path, mode: u32) -> Result<()> {
+    use std::os::unix::fs::PermissionsExt;
+    fs::set_permissions(path, fs::Permissions::from_mode(mode))?;
+    Ok(())
+}
+
+#[cfg(not(unix))]
+fn restrict(_path: &Path, _mode: u32) -> Result<()> {
+    Ok(())
+}
+
+/// Passwords in the desktop keyring, e.g. GNOME Keyring or KWallet.
+#[cfg(feature = "secret-service")]
+pub struct SecretServiceStore;
+
+#[cfg(feature = "secret-service")]
+impl SecretServiceStore {
+    fn entry(journal_name: &str) -> Result<keyring::Entry> {
+        Ok(keyring::Entry::new("jrnl", journal_name)?)
+    }
+}
+
+#[cfg(feature = "secret-service")]
+impl CredentialStore for SecretServiceStore {
+    fn get(&self, journal_name: &str) -> Result<Option<String>> {
+        match Self::entry(journal_name)?.get_password() {
+            Ok(password) => Ok(Some(password)),
+            Err(keyring::Error::NoEntry) => Ok(None),
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    fn set(&self, journal_name: &str, password: &str) -> Result<()> {
+        Ok(Self::entry(journal_name)?.set_password(password)?)
+    }
+
+    fn delete(&self, journal_name: &str) -> Result<bool> {
+        match Self::entry(journal_name)?.delete_credential() {
+            Ok(()) => Ok(true),
+            Err(keyring::Error::NoEntry) => Ok(false),
+            Err(err) => Err(err.into()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_file_store() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = FileStore::new(dir.path().join("jrnl").join("passwords.yaml"));
+        assert_eq!(store.get("work").unwrap(), None);
+        assert!(!store.delete("work").unwrap());
+
+        store.set("work", "hunter2").unwrap();
+        store.set("default", "swordfish").unwrap();
+        store.set("work", "hunter3").unwrap();
+        assert_eq!(store.get("work").unwrap().as_deref(), Some("hunter3"));
+        assert_eq!(store.get("default").unwrap().as_deref(), Some("swordfish"));
+
+        assert!(store.delete("work").unwrap());
+        assert_eq!(store.get("work").unwrap(), None);
+        assert_eq!(store.get("default").unwrap().as_deref(), Some("swordfish"));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_file_store_permissions() {
+        use std::os::unix::fs::PermissionsExt;
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("jrnl").join("passwords.yaml");
+        FileStore::new(path.clone()).set("work", "hunter2").unwrap();
+        let mode = |p: &Path| fs::metadata(p).unwrap().permissions().mode() & 0o777;
+        assert_eq!(mode(&path), 0o600);
+        assert_eq!(mode(path.parent().unwrap()), 0o700);
+    }
+}
@@ -0,0 +1,269 @@
+use argon2::{Algorithm, Argon2, Params, Version};
+use chacha20poly1305::aead::rand_core::RngCore;
+use chacha20poly1305::aead::{Aead, AeadCore, KeyInit, OsRng, Payload};
+use chacha20poly1305::{Key, XChaCha20Poly1305, XNonce};
+
+use crate::error::{JrnlError, JrnlErrorKind, Result};
+
+mod legacy;
+
+/// Encrypted journals start with this, followed by a format version byte.
+const MAGIC: &[u8] = b"JRNLRS";
+const VERSION: u8 = 1;
+const SALT_LEN: usize = 16;
+const NONCE_LEN: usize = 24;
+const HEADER_LEN: usize = MAGIC.len() + 1 + 3 * 4 + SALT_LEN + NONCE_LEN;
+
+/// Argon2id cost parameters, stored in the header so they can be raised later
+/// without breaking existing journals.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct KdfParams {
+    m_cost: u32,
+    t_cost: u32,
+    p_cost: u32,
+}
+
+impl KdfParams {
+    /// The most any cost may be raised to, four times the defaults. The header
+    /// isn't verified until the key is derived, so larger costs read from it
+    /// are rejected rather than letting a corrupt file exhaust memory or time.
+    const MAX: KdfParams = KdfParams {
+        m_cost: 4 * 19 * 1024,
+        t_cost: 4 * 2,
+        p_cost: 4,
+    };
+
+    fn within_limits(self) -> bool {
+        self.m_cost <= Self::MAX.m_cost
+            && self.t_cost <= Self::MAX.t_cost
+            && self.p_cost <= Self::MAX.p_cost
+    }
+}
+
+impl Default for KdfParams {
+    /// OWASP's recommended minimum for Argon2id: 19 MiB, 2 passes.
+    fn default() -> Self {
+        Self {
+            m_cost: 19 * 1024,
+            t_cost: 2,
+            p_cost: 1,
+        }
+    }
+}
+
+/// Whether `data` has the header of an encrypted journal, either one
+/// encrypted by `encrypt` or by Python jrnl 2.x and later.
+pub fn is_encrypted(data: &[u8]) -> bool {
+    data.starts_with(MAGIC) || legacy::detect(data) == Some(legacy::Scheme::V2)
+}
+
+/// Whether `data` may be an encrypted journal, counting header-less ones by
+/// Python jrnl 1.x. Those can't be told apart from other binary data, such as
+/// a plain journal that isn't UTF-8, so this is only for journals that are
+/// known to be encrypted.
+pub fn may_be_encrypted(data: &[u8]) -> bool {
+    is_encrypted(data) || legacy::detect(data).is_some()
+}
+
+/// Encrypt `plaintext` with a key derived from `password`.
+///
+/// Layout: magic, version, Argon2id m/t/p costs (little-endian u32), salt,
+/// XChaCha20-Poly1305 nonce, then the ciphertext. The whole header is
+/// authenticated along with the ciphertext.
+pub fn encrypt(plaintext: &[u8], password: &str) -> Result<Vec<u8>> {
+    encrypt_with(plaintext, password, KdfParams::default())
+}
+
+fn encrypt_with(plaintext: &[u8], password: &str, params: KdfParams) -> Result<Vec<u8>> {
+    let mut salt = [0u8; SALT_LEN];
+    OsRng.fill_bytes(&mut salt);
+    let nonce = XChaCha20Poly1305::generate_nonce(&mut OsRng);
+
+    let mut out = Vec::with_capacity(HEADER_LEN + plaintext.len() + 16);
+    out.extend_from_slice(MAGIC);
+    out.push(VERSION);
+    out.extend_from_slice(&params.m_cost.to_le_bytes());
+    out.extend_from_slice(&params.t_cost.to_le_bytes());
+    out.extend_from_slice(&params.p_cost.to_le_bytes());
+    out.extend_from_slice(&salt);
+    out.extend_from_slice(&nonce);
+
+    let cipher = XChaCha20Poly1305::new(&derive_key(password, &salt, params)?);
+    let payload = Payload {
+        msg: plaintext,
+        aad: &out,
+    };
+    let ciphertext = cipher
+        .encrypt(&nonce, payload)
+        .map_err(|_| JrnlError::new(JrnlErrorKind::InvalidEncryptedJournal))?;
+    out.extend_from_slice(&ciphertext);
+    Ok(out)
+}
+
+/// Decrypt data produced by `encrypt`, verifying that neither the header nor
+/// the ciphertext have been tampered with. Journals encrypted by Python jrnl
+/// are decrypted too.
+pub fn decrypt(data: &[u8], password: &str) -> Result<Vec<u8>> {
+    let invalid = || JrnlError::new(JrnlErrorKind::InvalidEncryptedJournal);
+    if !data.starts_with(MAGIC) {
+        let scheme = legacy::detect(data).ok_or_else(invalid)?;
+        return legacy::decrypt(scheme, data, password);
+    }
+    if data.len() < HEADER_LEN {
+        return Err(invalid());
+    }
+    let (header, ciphertext) = data.split_at(HEADER_LEN);
+    let rest = &header[MAGIC.len()..];
+    if rest[0] != VERSION {
+        return Err(JrnlError::new(JrnlErrorKind::UnsupportedEncryptionVersion));
+    }
+    let read_u32 = |offset: usize| {
+        let bytes = rest[offset..offset + 4]
+            .try_into()
+            .expect("slice is 4 bytes");
+        u32::from_le_bytes(bytes)
+    };
+    let params = KdfParams {
+        m_cost: read_u32(1),
+        t_cost: read_u32(5),
+        p_cost: read_u32(9),
+    };
+    if !params.within_limits() {
+        return Err(invalid());
+    }
+    let salt = &rest[13..13 + SALT_LEN];
+    let nonce = XNonce::from_slice(&rest[13 + SALT_LEN..]);
+
+    let cipher = XChaCha20Poly1305::new(&derive_key(password, salt, params)?);
+    let payload = Payload {
+        msg: ciphertext,
+        aad: header,
+    };
+    cipher
+        .decrypt(nonce, payload)
+        .map_err(|_| JrnlError::new(JrnlErrorKind::WrongPassword))
+}
+
+fn derive_key(password: &str, salt: &[u8], params: KdfParams) -> Result<Key> {
+    let invalid = || JrnlError::new(JrnlErrorKind::InvalidEncryptedJournal);
+    let params = Params::new(params.m_cost, params.t_cost, params.p_cost, Some(32))
+        .map_err(|_| invalid())?;
+    let mut key = Key::default();
+    Argon2::new(Algorithm::Argon2id, Version::V0x13, params)
+        .hash_password_into(password.as_bytes(), salt, &mut key)
+        .map_err(|_| invalid())?;
+    Ok(key)
+}
+
+/// Ask for a password on the terminal without echoing it.
+pub fn prompt_password(prompt: &str) -> std::io::Result<String> {
+    rpassword::prompt_password(prompt)
+}
+
+/// Ask for a new password twice, until both entries match.
+pub fn prompt_new_password() -> std::result::Result<String, Box<dyn std::error::Error>> {
+    for _ in 0..3 {
+        let password = prompt_password("Enter new password: ")?;
+        if password.is_empty() {
+            eprintln!("Password can't be empty, please try again");
+            continue;
+        }
+        if password == prompt_password("Enter password again: ")? {
+            return Ok(password);
+        }
+        eprintln!("Passwords did not match, please try again");
+    }
+    Err(JrnlError::new(JrnlErrorKind::Aborted)
+        .context("no password set")
+        .into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Keep tests fast; the format doesn't depend on the costs.
+    const CHEAP: KdfParams = KdfParams {
+        m_cost: 64,
+        t_cost: 1,
+        p_cost: 1,
+    };
+
+    #[test]
+    fn test_round_trip() {
+        let plaintext = b"[2023-01-12 08:51:57 AM] Secret.\nDon't tell.\n";
+        let data = encrypt_with(plaintext, "hunter2", CHEAP).unwrap();
+        assert!(is_encrypted(&data));
+        assert_eq!(data[MAGIC.len()], VERSION);
+        assert!(!data.windows(7).any(|w| w == b"Secret."));
+        assert_eq!(decrypt(&data, "hunter2").unwrap(), plaintext);
+
+        // fresh salt and nonce every time
+        let again = encrypt_with(plaintext, "hunter2", CHEAP).unwrap();
+        assert_ne!(data, again);
+    }
+
+    #[test]
+    fn test_headerless_journals_are_only_maybe_encrypted() {
+        let v1 = include_bytes!("../tests/fixtures/legacy/v1.journal");
+        let v2 = include_bytes!("../tests/fixtures/legacy/v2.journal");
+        assert!(!is_encrypted(v1) && may_be_encrypted(v1));
+        assert!(is_encrypted(v2) && may_be_encrypted(v2));
+
+        // a Latin-1 journal of whole AES blocks is plain unless known otherwise
+        let latin1 = b"[2023-01-12 08:00] Caf\xe9 au lait, cr\xe8me br\xfbl\xe9e.".repeat(16);
+        assert!(latin1.len().is_multiple_of(16));
+        assert!(!is_encrypted(&latin1) && may_be_encrypted(&latin1));
+    }
+
+    #[test]
+    fn test_excessive_costs_rejected() {
+        let mut data = encrypt_with(b"text", "hunter2", CHEAP).unwrap();
+        // m_cost follows the magic and version byte
+        let m_cost = MAGIC.len() + 1;
+        data[m_cost..m_cost + 4].copy_from_slice(&u32::MAX.to_le_bytes());
+        let err = decrypt(&data, "hunter2").unwrap_err();
+        assert_eq!(err.kind(), JrnlErrorKind::InvalidEncryptedJournal);
+
+        let t_cost = m_cost + 4;
+        let mut data = encrypt_with(b"text", "hunter2", CHEAP).unwrap();
+        data[t_cost..t_cost + 4].copy_from_slice(&(KdfParams::MAX.t_cost + 1).to_le_bytes());
+        let err = decrypt(&data, "hunter2").unwrap_err();
+        assert_eq!(err.kind(), JrnlErrorKind::InvalidEncryptedJournal);
+    }
+
+    #[test]
+    fn test_wrong_password() {
+        let data = encrypt_with(b"secret", "hunter2", CHEAP).unwrap();
+        let err = decrypt(&data, "hunter3").unwrap_err();
+        assert_eq!(err.kind(), JrnlErrorKind::WrongPassword);
+    }
+
+    #[test]
+    fn test_tampering_detected() {
+        let data = encrypt_with(b"secret", "hunter2", CHEAP).unwrap();
+
+        let mut body = data.clone();
+        *body.last_mut().unwrap() ^= 1;
+        assert!(decrypt(&body, "hunter2").is_err());
+
+        // the header is authenticated too, e.g. weakened KDF costs
+        let mut header = data.clone();
+        header[MAGIC.len() + 5] ^= 1;
+        assert!(decrypt(&header, "hunter2").is_err());
+
+        let mut version = data.clone();
+        version[MAGIC.len()] = 99;
+        assert_eq!(
+            decrypt(&version, "hunter2").unwrap_err().kind(),
+            JrnlErrorKind::UnsupportedEncryptionVersion
+        );
+
+        assert_eq!(
+            decrypt(&data[..HEADER_LEN - 1], "hunter2")
+                .unwrap_err()
+                .kind(),
+            JrnlErrorKind::InvalidEncryptedJournal
+        );
+    }
+}
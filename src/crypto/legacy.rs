@@ -0,0 +1,151 @@
+use aes::cipher::block_padding::Pkcs7;
+use aes::cipher::{BlockDecryptMut, KeyIvInit};
+use base64::engine::general_purpose::URL_SAFE;
+use base64::Engine;
+use hmac::{Hmac, Mac};
+use sha2::{Digest, Sha256};
+
+use crate::error::{JrnlError, JrnlErrorKind, Result};
+
+/// Fernet tokens are base64 of a 0x80 version byte followed by a big-endian
+/// timestamp, so they all start like this for the foreseeable future.
+const FERNET_PREFIX: &[u8] = b"gAAAAA";
+/// The fixed salt Python jrnl 2.x uses to derive Fernet keys.
+const V2_SALT: &[u8] = b"\xf2\xd5q\x0e\xc1\x8d.\xde\xdc\x8e6t\x89\x04\xce\xf8";
+const V2_ROUNDS: u32 = 100_000;
+const BLOCK_LEN: usize = 16;
+
+/// The encryption schemes of Python jrnl.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Scheme {
+    /// jrnl 1.x: AES-256-CBC keyed with SHA-256 of the password, with the IV
+    /// prepended and no header at all.
+    V1,
+    /// jrnl 2.x and later: Fernet keyed with PBKDF2-HMAC-SHA256.
+    V2,
+}
+
+/// Which legacy scheme, if any, `data` was encrypted with. v1 journals have no
+/// header, so anything that's whole AES blocks and isn't text is taken as one;
+/// only trust that for data that is known to be encrypted.
+pub fn detect(data: &[u8]) -> Option<Scheme> {
+    if data.starts_with(FERNET_PREFIX) {
+        Some(Scheme::V2)
+    } else if data.len() >= 2 * BLOCK_LEN
+        && data.len().is_multiple_of(BLOCK_LEN)
+        && std::str::from_utf8(data).is_err()
+    {
+        Some(Scheme::V1)
+    } else {
+        None
+    }
+}
+
+pub fn decrypt(scheme: Scheme, data: &[u8], password: &str) -> Result<Vec<u8>> {
+    match scheme {
+        Scheme::V1 => decrypt_v1(data, password),
+        Scheme::V2 => decrypt_v2(data, password),
+    }
+}
+
+fn decrypt_v1(data: &[u8], password: &str) -> Result<Vec<u8>> {
+    let key = Sha256::digest(password.as_bytes());
+    let (iv, ciphertext) = data.split_at(BLOCK_LEN);
+    let cipher = cbc::Decryptor::<aes::Aes256>::new_from_slices(&key, iv)
+        .map_err(|_| JrnlError::new(JrnlErrorKind::InvalidEncryptedJournal))?;
+    let mut plain = cipher
+        .decrypt_padded_vec_mut::<aes::cipher::block_padding::NoPadding>(ciphertext)
+        .map_err(|_| JrnlError::new(JrnlErrorKind::InvalidEncryptedJournal))?;
+
+    // The oldest versions padded with spaces instead of PKCS#7, and some with
+    // line breaks, which are stripped like upstream does, along with those
+    // ending the text. That also covers PKCS#7 padding of ten 0x0a bytes.
+    match plain.last() {
+        Some(b' ') => plain.truncate(plain.trim_ascii_end().len()),
+        Some(b'\n') => {
+            let padding = plain.iter().rev().take_while(|&&b| b == b'\n').count();
+            plain.truncate(plain.len() - padding);
+        }
+        Some(&n) if (1..=BLOCK_LEN as u8).contains(&n) => {
+            let (text, padding) = plain.split_at(plain.len() - n as usize);
+            if padding.iter().any(|&b| b != n) {
+                return Err(JrnlError::new(JrnlErrorKind::WrongPassword));
+            }
+            plain.truncate(text.len());
+        }
+        _ => return Err(JrnlError::new(JrnlErrorKind::WrongPassword)),
+    }
+    // There's no MAC, so garbage that happens to look padded is only caught
+    // here.
+    match std::str::from_utf8(&plain) {
+        Ok(_) => Ok(plain),
+        Err(_) => Err(JrnlError::new(JrnlErrorKind::WrongPassword)),
+    }
+}
+
+fn decrypt_v2(data: &[u8], password: &str) -> Result<Vec<u8>> {
+    let invalid = || JrnlError::new(JrnlErrorKind::InvalidEncryptedJournal);
+    let token = URL_SAFE.decode(data.trim_ascii()).map_err(|_| invalid())?;
+    // version, timestamp, IV, at least one block, HMAC
+    if token.len() < 1 + 8 + BLOCK_LEN + BLOCK_LEN + 32 || token[0] != 0x80 {
+        return Err(invalid());
+    }
+
+    let mut key = [0u8; 32];
+    pbkdf2::pbkdf2_hmac::<Sha256>(password.as_bytes(), V2_SALT, V2_ROUNDS, &mut key);
+    let (signing_key, encryption_key) = key.split_at(16);
+
+    let (signed, tag) = token.split_at(token.len() - 32);
+    let mut mac = Hmac::<Sha256>::new_from_slice(signing_key).map_err(|_| invalid())?;
+    mac.update(signed);
+    mac.verify_slice(tag)
+        .map_err(|_| JrnlError::new(JrnlErrorKind::WrongPassword))?;
+
+    let (iv, ciphertext) = signed[9..].split_at(BLOCK_LEN);
+    cbc::Decryptor::<aes::Aes128>::new_from_slices(encryption_key, iv)
+        .map_err(|_| invalid())?
+        .decrypt_padded_vec_mut::<Pkcs7>(ciphertext)
+        .map_err(|_| invalid())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PASSWORD: &str = "bad doggie no biscuit";
+    const PLAIN: &str = include_str!("../../tests/fixtures/legacy/plain.txt");
+    const V1: &[u8] = include_bytes!("../../tests/fixtures/legacy/v1.journal");
+    const V1_NEWLINES: &[u8] = include_bytes!("../../tests/fixtures/legacy/v1-newlines.journal");
+    const V2: &[u8] = include_bytes!("../../tests/fixtures/legacy/v2.journal");
+
+    #[test]
+    fn test_detect() {
+        assert_eq!(detect(V1), Some(Scheme::V1));
+        assert_eq!(detect(V2), Some(Scheme::V2));
+        assert_eq!(detect(PLAIN.as_bytes()), None);
+        assert_eq!(detect(b""), None);
+    }
+
+    #[test]
+    fn test_decrypt() {
+        assert_eq!(decrypt(Scheme::V1, V1, PASSWORD).unwrap(), PLAIN.as_bytes());
+        assert_eq!(decrypt(Scheme::V2, V2, PASSWORD).unwrap(), PLAIN.as_bytes());
+        assert_eq!(detect(V1_NEWLINES), Some(Scheme::V1));
+        assert_eq!(
+            decrypt(Scheme::V1, V1_NEWLINES, PASSWORD).unwrap(),
+            PLAIN.trim_end_matches('\n').as_bytes()
+        );
+    }
+
+    #[test]
+    fn test_wrong_password() {
+        for (scheme, data) in [
+            (Scheme::V1, V1),
+            (Scheme::V1, V1_NEWLINES),
+            (Scheme::V2, V2),
+        ] {
+            let err = decrypt(scheme, data, "good doggie").unwrap_err();
+            assert_eq!(err.kind(), JrnlErrorKind::WrongPassword);
+        }
+    }
+}
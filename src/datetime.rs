@@ -0,0 +1,431 @@
+use chrono::{
+    Datelike, Duration, Month, Months, NaiveDate, NaiveDateTime, NaiveTime, Timelike, Weekday,
+};
+
+/// Parse a human-entered date/time expression relative to `now`.
+///
+/// Understands absolute dates (`2023-01-12`, `2023/01/12`, `1/12/2023`,
+/// `march 5`, `5 march 2022`), named days (`today`, `yesterday`, `friday`,
+/// `last friday`, `next week`), offsets (`3 days ago`, `in 2 weeks`,
+/// `2 hours ago`) and times of day (`5pm`, `5:30 am`, `17:45`, `noon`). When
+/// the expression names a day but no time of day, `default_time` is used; when
+/// it names only a time, the day is today.
+///
+/// Dates without a year and bare weekdays are taken to be in the past, since
+/// journal entries are usually about things that already happened.
+pub fn parse(input: &str, now: NaiveDateTime, default_time: NaiveTime) -> Option<NaiveDateTime> {
+    let input = input.trim();
+    for fmt in ["%Y-%m-%d %H:%M:%S", "%Y-%m-%dT%H:%M:%S", "%Y-%m-%d %H:%M"] {
+        if let Ok(dt) = NaiveDateTime::parse_from_str(input, fmt) {
+            return Some(dt);
+        }
+    }
+    let input = input.to_lowercase().replace(',', " ");
+
+    let now = now.with_nanosecond(0).unwrap_or(now);
+    let mut parsed = Parsed::default();
+    let tokens = input.split_whitespace().collect::<Vec<_>>();
+    let mut rest = &tokens[..];
+    while !rest.is_empty() {
+        let consumed = parsed.consume(rest, now)?;
+        rest = &rest[consumed..];
+    }
+
+    match (parsed.date, parsed.time) {
+        (None, None) => None,
+        (Some(d), t) => Some(d.and_time(t.unwrap_or(default_time))),
+        (None, Some(t)) => Some(now.date().and_time(t)),
+    }
+}
+
+#[derive(Default)]
+struct Parsed {
+    date: Option<NaiveDate>,
+    time: Option<NaiveTime>,
+}
+
+impl Parsed {
+    /// Consume the expression at the start of `tokens`, returning how many
+    /// tokens it used, or `None` if it can't be understood or conflicts with
+    /// something already parsed.
+    fn consume(&mut self, tokens: &[&str], now: NaiveDateTime) -> Option<usize> {
+        let today = now.date();
+        match tokens {
+            ["at" | "on", ..] => Some(1),
+            ["now", ..] => self.set_datetime(now, 1),
+            ["today" | "tonight", ..] => self.set_date(today, 1),
+            ["yesterday", ..] => self.set_date(today - Duration::days(1), 1),
+            ["tomorrow", ..] => self.set_date(today + Duration::days(1), 1),
+            ["in", n, unit, ..] => {
+                let when = offset(now, parse_count(n)?, unit)?;
+                self.set_offset(when, unit, 3)
+            }
+            [n, unit, "ago", ..] => {
+                let when = offset(now, parse_count(n)?.checked_neg()?, unit)?;
+                self.set_offset(when, unit, 3)
+            }
+            [which @ ("last" | "next" | "this"), unit, ..] => {
+                let date = match (*which, unit.parse::<Weekday>()) {
+                    ("last", Ok(weekday)) => previous_weekday(today - Duration::days(1), weekday),
+                    ("next", Ok(weekday)) => next_weekday(today + Duration::days(1), weekday),
+                    ("this", Ok(weekday)) => next_weekday(today, weekday),
+                    ("last", Err(_)) => offset(now, -1, unit)?.date(),
+                    ("next", Err(_)) => offset(now, 1, unit)?.date(),
+                    _ => return None,
+                };
+                self.set_date(date, 2)
+            }
+            [word, ..] if word.parse::<Weekday>().is_ok() => {
+                self.set_date(previous_weekday(today, word.parse().ok()?), 1)
+            }
+            [word, ..] if parse_month(word).is_some() => {
+                let (date, used) = month_day_year(tokens, today)?;
+                self.set_date(date, used)
+            }
+            [day, month, ..] if parse_day(day).is_some() && parse_month(month).is_some() => {
+                let (date, used) = day_month_year(tokens, today)?;
+                self.set_date(date, used)
+            }
+            [clock, meridiem @ ("am" | "pm" | "a.m." | "p.m."), ..] => {
+                let meridiem = meridiem.replace('.', "");
+                self.set_time(parse_time(&format!("{clock}{meridiem}"))?, 2)
+            }
+            [word, ..] => {
+                if let Some(date) = parse_numeric_date(word, today) {
+                    self.set_date(date, 1)
+                } else {
+                    self.set_time(parse_time(word)?, 1)
+                }
+            }
+            [] => None,
+        }
+    }
+
+    fn set_date(&mut self, date: NaiveDate, used: usize) -> Option<usize> {
+        if self.date.replace(date).is_some() {
+            return None;
+        }
+        Some(used)
+    }
+
+    fn set_time(&mut self, time: NaiveTime, used: usize) -> Option<usize> {
+        if self.time.replace(time).is_some() {
+            return None;
+        }
+        Some(used)
+    }
+
+    fn set_datetime(&mut self, datetime: NaiveDateTime, used: usize) -> Option<usize> {
+        self.set_date(datetime.date(), used)?;
+        self.set_time(datetime.time(), used)
+    }
+
+    /// Offsets in hours or minutes pin down the time of day as well.
+    fn set_offset(&mut self, when: NaiveDateTime, unit: &str, used: usize) -> Option<usize> {
+        match unit.trim_end_matches('s') {
+            "hour" | "minute" | "min" => self.set_datetime(when, used),
+            _ => self.set_date(when.date(), used),
+        }
+    }
+}
+
+fn offset(now: NaiveDateTime, count: i64, unit: &str) -> Option<NaiveDateTime> {
+    let months = |n: i64| -> Option<NaiveDateTime> {
+        let months = Months::new(u32::try_from(n.unsigned_abs()).ok()?);
+        match n < 0 {
+            true => now.checked_sub_months(months),
+            false => now.checked_add_months(months),
+        }
+    };
+    match unit.trim_end_matches('s') {
+        "min" | "minute" => now.checked_add_signed(Duration::try_minutes(count)?),
+        "hour" => now.checked_add_signed(Duration::try_hours(count)?),
+        "day" => now.checked_add_signed(Duration::try_days(count)?),
+        "week" => now.checked_add_signed(Duration::try_weeks(count)?),
+        "month" => months(count),
+        "year" => months(count.checked_mul(12)?),
+        _ => None,
+    }
+}
+
+fn parse_count(word: &str) -> Option<i64> {
+    let count = match word {
+        "a" | "an" | "one" => 1,
+        "two" => 2,
+        "three" => 3,
+        "four" => 4,
+        "five" => 5,
+        "six" => 6,
+        "seven" => 7,
+        "eight" => 8,
+        "nine" => 9,
+        "ten" => 10,
+        _ => word.parse().ok()?,
+    };
+    Some(count)
+}
+
+/// The most recent `weekday` on or before `from`.
+fn previous_weekday(from: NaiveDate, weekday: Weekday) -> NaiveDate {
+    let back = (7 + from.weekday().num_days_from_monday() - weekday.num_days_from_monday()) % 7;
+    from - Duration::days(back.into())
+}
+
+/// The first `weekday` on or after `from`.
+fn next_weekday(from: NaiveDate, weekday: Weekday) -> NaiveDate {
+    let ahead = (7 + weekday.num_days_from_monday() - from.weekday().num_days_from_monday()) % 7;
+    from + Duration::days(ahead.into())
+}
+
+fn parse_month(word: &str) -> Option<u32> {
+    let word = word.trim_end_matches('.');
+    let word = if word == "sept" { "sep" } else { word };
+    word.parse::<Month>().ok().map(|m| m.number_from_month())
+}
+
+fn parse_day(word: &str) -> Option<u32> {
+    let digits = ["st", "nd", "rd", "th"]
+        .iter()
+        .find_map(|suffix| word.strip_suffix(suffix))
+        .unwrap_or(word);
+    digits.parse().ok().filter(|day| (1..=31).contains(day))
+}
+
+fn parse_year(word: &str) -> Option<i32> {
+    match word.len() {
+        4 => word.parse().ok(),
+        _ => None,
+    }
+}
+
+/// `march 5`, `march 5 2022`, `march 2022` or `march`.
+fn month_day_year(tokens: &[&str], today: NaiveDate) -> Option<(NaiveDate, usize)> {
+    let month = parse_month(tokens[0])?;
+    match tokens[1..] {
+        [day, year, ..] if parse_day(day).is_some() && parse_year(year).is_some() => {
+            let date = NaiveDate::from_ymd_opt(parse_year(year)?, month, parse_day(day)?)?;
+            Some((date, 3))
+        }
+        [day, ..] if parse_day(day).is_some() && !is_meridiem(tokens.get(2)) => {
+            Some((in_past(today, month, parse_day(day)?)?, 2))
+        }
+        [year, ..] if parse_year(year).is_some() => {
+            Some((NaiveDate::from_ymd_opt(parse_year(year)?, month, 1)?, 2))
+        }
+        _ => Some((in_past(today, month, 1)?, 1)),
+    }
+}
+
+/// `5 march`, `5th march 2022`.
+fn day_month_year(tokens: &[&str], today: NaiveDate) -> Option<(NaiveDate, usize)> {
+    let day = parse_day(tokens[0])?;
+    let month = parse_month(tokens[1])?;
+    match tokens.get(2).and_then(|year| parse_year(year)) {
+        Some(year) => Some((NaiveDate::from_ymd_opt(year, month, day)?, 3)),
+        None => Some((in_past(today, month, day)?, 2)),
+    }
+}
+
+/// Whether the token after a month name and number is `am`/`pm`, as in
+/// `march 5 pm`, in which case the number is an hour rather than a day.
+fn is_meridiem(token: Option<&&str>) -> bool {
+    matches!(token, Some(&"am" | &"pm"))
+}
+
+/// This year's `month`/`day`, or last year's if that hasn't happened yet.
+fn in_past(today: NaiveDate, month: u32, day: u32) -> Option<NaiveDate> {
+    NaiveDate::from_ymd_opt(today.year(), month, day)
+        .filter(|date| date <= &today)
+        .or_else(|| NaiveDate::from_ymd_opt(today.year() - 1, month, day))
+}
+
+fn parse_numeric_date(word: &str, today: NaiveDate) -> Option<NaiveDate> {
+    for fmt in ["%Y-%m-%d", "%Y/%m/%d", "%m/%d/%Y"] {
+        if let Ok(date) = NaiveDate::parse_from_str(word, fmt) {
+            return Some(date);
+        }
+    }
+    let (month, day) = word.split_once('/')?;
+    in_past(today, month.parse().ok()?, day.parse().ok()?)
+}
+
+fn parse_time(token: &str) -> Option<NaiveTime> {
+    match token {
+        "noon" | "midday" => return NaiveTime::from_hms_opt(12, 0, 0),
+        "midnight" => return NaiveTime::from_hms_opt(0, 0, 0),
+        _ => (),
+    }
+    let (clock, offset) = if let Some(clock) = token.strip_suffix("am") {
+        (clock, Some(0))
+    } else if let Some(clock) = token.strip_suffix("pm") {
+        (clock, Some(12))
+    } else {
+        (token, None)
+    };
+    let mut parts = clock.split(':');
+    let hour = parts.next()?.parse::<u32>().ok()?;
+    let minute = parts.next().map(|m| m.parse::<u32>()).transpose().ok()?;
+    let second = parts.next().map(|s| s.parse::<u32>()).transpose().ok()?;
+    if parts.next().is_some() {
+        return None;
+    }
+    let hour = match offset {
+        Some(offset) if (1..=12).contains(&hour) => hour % 12 + offset,
+        Some(_) => return None,
+        // a bare number is too ambiguous to be a time of day
+        None if minute.is_none() => return None,
+        None => hour,
+    };
+    NaiveTime::from_hms_opt(hour, minute.unwrap_or(0), second.unwrap_or(0))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Thursday, 2023-01-12 14:30:15
+    fn now() -> NaiveDateTime {
+        NaiveDate::from_ymd_opt(2023, 1, 12)
+            .unwrap()
+            .and_hms_opt(14, 30, 15)
+            .unwrap()
+    }
+
+    fn default_time() -> NaiveTime {
+        NaiveTime::from_hms_opt(9, 0, 0).unwrap()
+    }
+
+    fn ymd_hms(y: i32, m: u32, d: u32, h: u32, min: u32, s: u32) -> NaiveDateTime {
+        NaiveDate::from_ymd_opt(y, m, d)
+            .unwrap()
+            .and_hms_opt(h, min, s)
+            .unwrap()
+    }
+
+    fn assert_parses(cases: &[(&str, NaiveDateTime)]) {
+        for (input, expected) in cases {
+            assert_eq!(
+                parse(input, now(), default_time()),
+                Some(*expected),
+                "{input}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_parse() {
+        assert_parses(&[
+            ("now", ymd_hms(2023, 1, 12, 14, 30, 15)),
+            ("today", ymd_hms(2023, 1, 12, 9, 0, 0)),
+            ("yesterday", ymd_hms(2023, 1, 11, 9, 0, 0)),
+            ("yesterday 5pm", ymd_hms(2023, 1, 11, 17, 0, 0)),
+            ("Yesterday at 5:30 PM", ymd_hms(2023, 1, 11, 17, 30, 0)),
+            ("tomorrow noon", ymd_hms(2023, 1, 13, 12, 0, 0)),
+            ("12am", ymd_hms(2023, 1, 12, 0, 0, 0)),
+            ("17:45", ymd_hms(2023, 1, 12, 17, 45, 0)),
+            ("2022-03-05", ymd_hms(2022, 3, 5, 9, 0, 0)),
+            ("2022-03-05 10:11", ymd_hms(2022, 3, 5, 10, 11, 0)),
+            ("2022-03-05 8pm", ymd_hms(2022, 3, 5, 20, 0, 0)),
+        ]);
+    }
+
+    #[test]
+    fn test_parse_absolute_dates() {
+        assert_parses(&[
+            ("2022-03-05T07:08:09", ymd_hms(2022, 3, 5, 7, 8, 9)),
+            ("2022-03-05 07:08:09", ymd_hms(2022, 3, 5, 7, 8, 9)),
+            ("2022/03/05", ymd_hms(2022, 3, 5, 9, 0, 0)),
+            ("3/5/2022", ymd_hms(2022, 3, 5, 9, 0, 0)),
+            ("1/10", ymd_hms(2023, 1, 10, 9, 0, 0)),
+            ("3/5", ymd_hms(2022, 3, 5, 9, 0, 0)),
+            ("march 5", ymd_hms(2022, 3, 5, 9, 0, 0)),
+            ("Jan 5", ymd_hms(2023, 1, 5, 9, 0, 0)),
+            ("jan 12", ymd_hms(2023, 1, 12, 9, 0, 0)),
+            ("jan 13", ymd_hms(2022, 1, 13, 9, 0, 0)),
+            ("March 5, 2021", ymd_hms(2021, 3, 5, 9, 0, 0)),
+            ("march 5th 2021 at 4pm", ymd_hms(2021, 3, 5, 16, 0, 0)),
+            ("5 march", ymd_hms(2022, 3, 5, 9, 0, 0)),
+            ("1st sept. 2020", ymd_hms(2020, 9, 1, 9, 0, 0)),
+            ("march 2020", ymd_hms(2020, 3, 1, 9, 0, 0)),
+            ("december", ymd_hms(2022, 12, 1, 9, 0, 0)),
+            ("march 5 pm", ymd_hms(2022, 3, 1, 17, 0, 0)),
+        ]);
+    }
+
+    #[test]
+    fn test_parse_named_days() {
+        assert_parses(&[
+            ("thursday", ymd_hms(2023, 1, 12, 9, 0, 0)),
+            ("friday", ymd_hms(2023, 1, 6, 9, 0, 0)),
+            ("mon", ymd_hms(2023, 1, 9, 9, 0, 0)),
+            ("last thursday", ymd_hms(2023, 1, 5, 9, 0, 0)),
+            ("last friday", ymd_hms(2023, 1, 6, 9, 0, 0)),
+            ("last friday at noon", ymd_hms(2023, 1, 6, 12, 0, 0)),
+            ("next thursday", ymd_hms(2023, 1, 19, 9, 0, 0)),
+            ("next friday", ymd_hms(2023, 1, 13, 9, 0, 0)),
+            ("this saturday", ymd_hms(2023, 1, 14, 9, 0, 0)),
+            ("last week", ymd_hms(2023, 1, 5, 9, 0, 0)),
+            ("next month", ymd_hms(2023, 2, 12, 9, 0, 0)),
+            ("last year", ymd_hms(2022, 1, 12, 9, 0, 0)),
+        ]);
+    }
+
+    #[test]
+    fn test_parse_relative() {
+        assert_parses(&[
+            ("3 days ago", ymd_hms(2023, 1, 9, 9, 0, 0)),
+            ("a week ago", ymd_hms(2023, 1, 5, 9, 0, 0)),
+            ("two weeks ago 8am", ymd_hms(2022, 12, 29, 8, 0, 0)),
+            ("1 month ago", ymd_hms(2022, 12, 12, 9, 0, 0)),
+            ("2 years ago", ymd_hms(2021, 1, 12, 9, 0, 0)),
+            ("in 3 days", ymd_hms(2023, 1, 15, 9, 0, 0)),
+            ("2 hours ago", ymd_hms(2023, 1, 12, 12, 30, 15)),
+            ("45 minutes ago", ymd_hms(2023, 1, 12, 13, 45, 15)),
+            ("in an hour", ymd_hms(2023, 1, 12, 15, 30, 15)),
+        ]);
+    }
+
+    #[test]
+    fn test_parse_times() {
+        assert_parses(&[
+            ("noon", ymd_hms(2023, 1, 12, 12, 0, 0)),
+            ("midnight", ymd_hms(2023, 1, 12, 0, 0, 0)),
+            ("5 pm", ymd_hms(2023, 1, 12, 17, 0, 0)),
+            ("5:15 a.m.", ymd_hms(2023, 1, 12, 5, 15, 0)),
+            ("12pm", ymd_hms(2023, 1, 12, 12, 0, 0)),
+            ("23:59:59", ymd_hms(2023, 1, 12, 23, 59, 59)),
+            ("at 7:05", ymd_hms(2023, 1, 12, 7, 5, 0)),
+            ("5pm on march 5 2021", ymd_hms(2021, 3, 5, 17, 0, 0)),
+        ]);
+    }
+
+    #[test]
+    fn test_parse_rejects() {
+        for input in [
+            "",
+            "hello",
+            "5",
+            "13pm",
+            "0am",
+            "25:00",
+            "today yesterday",
+            "5pm 6pm",
+            "2023-02-30",
+            "february 30 2023",
+            "last",
+            "3 fortnights ago",
+            "2 hours ago yesterday",
+            "this week",
+            // out of range
+            "100000000 days ago",
+            "in 100000000 days",
+            "9999999999999999 days ago",
+            "9999999999999999 weeks ago",
+            "in 9999999999999999 hours",
+            "9223372036854775807 minutes ago",
+            "99999999999 years ago",
+        ] {
+            assert_eq!(parse(input, now(), default_time()), None, "{input}");
+        }
+    }
+}
@@ -1,3 +1,47 @@
-pub fn decrypt() {
-    todo!()
+use std::path::Path;
+
+use crate::credentials;
+use crate::journal;
+use crate::settings::{self, Settings};
+use crate::store;
+
+type Result<T> = std::result::Result<T, Box<dyn std::error::Error>>;
+
+/// Decrypt the journal, either in place, marking it as unencrypted in the
+/// config file, or into a separate plain text file, leaving the journal as is.
+pub fn decrypt(
+    settings: &Settings,
+    journal_name: &str,
+    journal_file: &Path,
+    config_file: &str,
+    output: Option<&str>,
+) -> Result<()> {
+    if store::sqlite_path(journal_file).is_some() {
+        Err(store::no_encryption(journal_file, "an sqlite"))?
+    }
+    if journal_file.is_dir() {
+        Err(store::no_encryption(journal_file, "a folder"))?
+    }
+    let store = credentials::store(settings, journal_name)?;
+    let (text, _) = journal::unlock(journal_file, journal_name, store.as_deref(), true)?;
+    match output {
+        Some(output) => {
+            journal::write_text(Path::new(output), &text, None)?;
+            eprintln!("[Journal '{journal_name}' decrypted to {output}]");
+        }
+        None => {
+            journal::write_text(journal_file, &text, None)?;
+            if settings.encrypt(journal_name)? {
+                settings::set_journal_encrypt(config_file, journal_name, false)?;
+            }
+            if let Some(store) = store {
+                store.delete(journal_name)?;
+            }
+            eprintln!(
+                "[Journal '{journal_name}' decrypted to {}]",
+                journal_file.display()
+            );
+        }
+    }
+    Ok(())
 }
@@ -0,0 +1,46 @@
+use std::io;
+
+use crate::entry::Entry;
+use crate::format::Formatter;
+use crate::prompt;
+use crate::store::JournalStore;
+
+type Result<T> = std::result::Result<T, Box<dyn std::error::Error>>;
+
+/// Delete the `entries` the user confirms one by one, or all of them with
+/// `yes`. With `dry_run`, the entries that would go are shown instead.
+pub fn delete(
+    entries: &[&Entry],
+    store: &mut dyn JournalStore,
+    formatter: &dyn Formatter,
+    yes: bool,
+    dry_run: bool,
+) -> Result<()> {
+    if entries.is_empty() {
+        eprintln!("[No entries to delete]");
+        return Ok(());
+    }
+    if dry_run {
+        print!("{}", formatter.format_entries(entries));
+        eprintln!(
+            "[{} entries would be deleted, nothing changed]",
+            entries.len()
+        );
+        return Ok(());
+    }
+    let selected = match yes {
+        true => entries.to_vec(),
+        false => prompt::confirm_each(
+            entries,
+            "Delete this entry?",
+            formatter,
+            &mut io::stdin().lock(),
+        )?,
+    };
+    let deleted = match selected.is_empty() {
+        true => 0,
+        false => store.delete(&selected.into_iter().cloned().collect::<Vec<_>>())?,
+    };
+    eprintln!("[{deleted} entries deleted]");
+    Ok(())
+}
@@ -0,0 +1,124 @@
+use std::collections::HashSet;
+
+use crate::editor;
+use crate::entry::Entry;
+use crate::error::{JrnlError, JrnlErrorKind};
+use crate::journal::{self, Journal};
+use crate::settings::Settings;
+use crate::store::JournalStore;
+
+type Result<T> = std::result::Result<T, Box<dyn std::error::Error>>;
+
+/// Open `entries` in the configured editor and replace them in the journal
+/// with whatever was saved: entries can be changed, removed, added or given
+/// another time. The rest of the journal is left alone.
+pub fn edit(
+    entries: &[Entry],
+    store: &mut dyn JournalStore,
+    settings: &Settings,
+    journal_name: &str,
+) -> Result<()> {
+    if entries.is_empty() {
+        eprintln!("[No entries to edit]");
+        return Ok(());
+    }
+    let editor = settings
+        .editor(journal_name)
+        .map_err(|_| "no editor configured; set 'editor' in your config file")?;
+    let timeformat = settings.timeformat(journal_name)?;
+    let text = Journal::from_entries(journal_name, entries.to_vec()).format(&timeformat);
+    let buffer = editor::edit(&editor, &text)?;
+    if buffer.trim() == text.trim() {
+        eprintln!("[No changes made]");
+        return Ok(());
+    }
+    let edited = Journal::read(journal_name, &buffer, &timeformat);
+    if !edited.preamble().trim().is_empty() {
+        let mut err = JrnlError::new(JrnlErrorKind::InvalidJournal)
+            .context("failed to read the edited entries, nothing changed");
+        if let Some(mut problem) = journal::check(journal_name, &buffer, &timeformat)
+            .into_iter()
+            .next()
+        {
+            problem.note = Some("each entry must start with a line like '[time] title'".into());
+            err = err.source(problem);
+        }
+        Err(err)?
+    }
+    let edited = edited.into_entries();
+
+    // entries may have moved to other days, whose entries must be kept too
+    let days = entries
+        .iter()
+        .chain(&edited)
+        .map(|entry| entry.time.date())
+        .collect::<HashSet<_>>();
+    let mut journal = store.load(&|date| days.contains(&date))?;
+    // compare with the entries as shown, which may have lost precision
+    let shown = Journal::read(journal_name, &text, &timeformat).into_entries();
+    let (modified, deleted, added) = count_changes(&shown, &edited);
+    journal.remove(entries);
+    journal.insert(edited);
+    store.replace(&journal)?;
+
+    let mut summary = format!("{modified} entries modified, {deleted} deleted");
+    if added > 0 {
+        summary.push_str(&format!(", {added} added"));
+    }
+    eprintln!("[{summary}]");
+    Ok(())
+}
+
+/// How many of the `before` entries were modified and deleted to end up with
+/// `after`, and how many were added on top. Identical entries are counted
+/// one by one.
+fn count_changes(before: &[Entry], after: &[Entry]) -> (usize, usize, usize) {
+    let mut new = after.to_vec();
+    let mut gone = 0;
+    for entry in before {
+        match new.iter().position(|other| other == entry) {
+            Some(index) => {
+                new.swap_remove(index);
+            }
+            None => gone += 1,
+        }
+    }
+    let modified = gone.min(new.len());
+    (modified, gone - modified, new.len() - modified)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::NaiveDate;
+
+    #[test]
+    fn test_count_changes() {
+        let entry = |day, title| {
+            let time = NaiveDate::from_ymd_opt(2024, 3, day)
+                .unwrap()
+                .and_hms_opt(9, 0, 0)
+                .unwrap();
+            Entry::new(time, title, "")
+        };
+        let before = [entry(1, "one"), entry(2, "two"), entry(3, "three")];
+
+        assert_eq!(count_changes(&before, &before), (0, 0, 0));
+        // retimed, reworded and dropped
+        let after = [entry(4, "one"), entry(2, "two!")];
+        assert_eq!(count_changes(&before, &after), (2, 1, 0));
+        let after = [
+            entry(1, "one"),
+            entry(2, "two"),
+            entry(3, "3"),
+            entry(5, "five"),
+        ];
+        assert_eq!(count_changes(&before, &after), (1, 0, 1));
+        assert_eq!(count_changes(&before, &[]), (0, 3, 0));
+
+        // one of two identical entries dropped, or a copy added
+        let twice = [entry(1, "one"), entry(1, "one")];
+        assert_eq!(count_changes(&twice, &twice[..1]), (0, 1, 0));
+        assert_eq!(count_changes(&twice[..1], &twice), (0, 0, 1));
+    }
+}
@@ -0,0 +1,76 @@
+use std::fs;
+use std::io::{self, Write};
+use std::process::Command;
+
+use tempfile::Builder;
+
+type Result<T> = std::result::Result<T, Box<dyn std::error::Error>>;
+
+/// Open `initial` in `editor` and return the saved buffer. The temporary file
+/// is removed when this returns, whether the editor succeeded, failed or we
+/// unwound from a panic.
+pub fn edit(editor: &str, initial: &str) -> Result<String> {
+    let (program, args) = split_command(editor)
+        .split_first()
+        .map(|(program, args)| (program.clone(), args.to_vec()))
+        .ok_or("no editor configured")?;
+
+    let mut file = Builder::new().prefix("jrnl").suffix(".jrnl").tempfile()?;
+    file.write_all(initial.as_bytes())?;
+    file.flush()?;
+
+    let status = Command::new(&program)
+        .args(&args)
+        .arg(file.path())
+        .status()
+        .map_err(|err| io::Error::new(err.kind(), format!("failed to run '{program}': {err}")))?;
+    if !status.success() {
+        return Err(format!("editor '{program}' exited with {status}").into());
+    }
+    Ok(fs::read_to_string(file.path())?)
+}
+
+/// Split an editor command such as `code --wait` or `"my editor" -f` into
+/// program and arguments, honoring single and double quotes.
+fn split_command(command: &str) -> Vec<String> {
+    let mut words = vec![];
+    let mut word: Option<String> = None;
+    let mut quote: Option<char> = None;
+    for c in command.chars() {
+        match (quote, c) {
+            (Some(q), c) if c == q => quote = None,
+            (Some(_), c) => word.get_or_insert_with(String::new).push(c),
+            (None, '"' | '\'') => {
+                quote = Some(c);
+                word.get_or_insert_with(String::new);
+            }
+            (None, c) if c.is_whitespace() => words.extend(word.take()),
+            (None, c) => word.get_or_insert_with(String::new).push(c),
+        }
+    }
+    words.extend(word);
+    words
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_split_command() {
+        assert_eq!(split_command("vim"), vec!["vim"]);
+        assert_eq!(split_command("  code  --wait "), vec!["code", "--wait"]);
+        assert_eq!(
+            split_command(r#""/opt/my editor/bin" -f 'a b' """#),
+            vec!["/opt/my editor/bin", "-f", "a b", ""]
+        );
+        assert!(split_command("   ").is_empty());
+    }
+
+    #[test]
+    fn test_edit_reads_back_buffer() {
+        // `true` leaves the file as written
+        assert_eq!(edit("true", "seeded").unwrap(), "seeded");
+        assert!(edit("false", "seeded").is_err());
+    }
+}
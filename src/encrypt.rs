@@ -1,3 +1,38 @@
-pub fn encrypt() {
-    todo!()
+use std::path::Path;
+
+use crate::credentials;
+use crate::journal;
+use crate::settings::{self, Settings};
+use crate::store;
+
+type Result<T> = std::result::Result<T, Box<dyn std::error::Error>>;
+
+/// Encrypt the journal with a new password and mark it as encrypted in the
+/// config file. An already encrypted journal is re-encrypted with the new
+/// password.
+pub fn encrypt(
+    settings: &Settings,
+    journal_name: &str,
+    journal_file: &Path,
+    config_file: &str,
+) -> Result<()> {
+    if store::sqlite_path(journal_file).is_some() {
+        Err(store::no_encryption(journal_file, "an sqlite"))?
+    }
+    if journal_file.is_dir() {
+        Err(store::no_encryption(journal_file, "a folder"))?
+    }
+    let store = credentials::store(settings, journal_name)?;
+    let encrypted = settings.encrypt(journal_name)?;
+    let (text, _) = journal::unlock(journal_file, journal_name, store.as_deref(), encrypted)?;
+    let password = credentials::new_password(journal_name, store.as_deref())?;
+    journal::write_text(journal_file, &text, Some(&password))?;
+    if !encrypted {
+        settings::set_journal_encrypt(config_file, journal_name, true)?;
+    }
+    eprintln!(
+        "[Journal '{journal_name}' encrypted to {}]",
+        journal_file.display()
+    );
+    Ok(())
 }
@@ -1,79 +1,86 @@
 use chrono::NaiveDateTime;
 use lazy_static::lazy_static;
 use regex::Regex;
-use std::iter::Peekable;
+use std::ops::Range;
 
 use crate::error::{JrnlError, JrnlErrorKind};
 
 type Result<T> = std::result::Result<T, Box<dyn std::error::Error>>;
 
-pub struct Entries<I>
-where
-    I: Iterator,
-{
-    iter: Peekable<I>,
+lazy_static! {
+    static ref TITLE_RE: Regex =
+        Regex::new(r"^[[:blank:]]*\[(?P<time>[^\]]+)\]\s*(?P<title>.*$)").unwrap();
 }
 
-impl<'a, I> Iterator for Entries<I>
-where
-    I: Iterator<Item = &'a str>,
-{
-    type Item = Entry;
-
-    fn next(&mut self) -> Option<Self::Item> {
-        let mut entry_lines: Vec<&str> = vec![];
-        match self.iter.next() {
-            None => None,
-            Some(elt) => {
-                entry_lines.push(elt);
-                loop {
-                    match self.iter.peek() {
-                        Some(&elt) if !elt.starts_with("[") => {
-                            entry_lines.push(self.iter.next().unwrap())
-                        }
-                        _ => break,
-                    }
-                }
-                Entry::parse(&entry_lines).ok()
-            }
-        }
-    }
+/// Formats tried, in order, on entry times that don't match the configured
+/// `timeformat`, e.g. in journals written before it was changed.
+const FALLBACK_TIMEFORMATS: &[&str] = &[
+    "%F %r",
+    "%F %T",
+    "%F %R",
+    "%F %I:%M %p",
+    "%FT%T",
+    "%FT%R",
+    "%Y/%m/%d %T",
+    "%Y/%m/%d %R",
+    "%d %b %Y %T",
+    "%d %b %Y %R",
+];
+
+/// Whether `line` starts an entry, i.e. is a header with a valid time.
+pub fn is_header(line: &str, timeformat: &str) -> bool {
+    line.starts_with('[') && Entry::parse(&[line], timeformat).is_ok()
 }
 
-pub fn entries<I>(iter: I) -> Entries<I>
-where
-    I: Iterator,
-{
-    Entries {
-        iter: iter.peekable(),
-    }
+/// The tag in `word`, if it is one: a tag symbol followed by letters, digits
+/// and `_`, `-`, `+` or `/`, possibly in brackets or followed by punctuation
+/// or a possessive `'s`. Mail addresses and URLs with a fragment don't start
+/// with the symbol, so aren't tags.
+fn tag<'w>(word: &'w str, tagsymbols: &str) -> Option<&'w str> {
+    let word = word
+        .trim_start_matches(['(', '[', '{', '"', '\''])
+        .trim_end_matches(|c: char| !c.is_alphanumeric() && c != '_');
+    let word = word
+        .strip_suffix("'s")
+        .or_else(|| word.strip_suffix("’s"))
+        .unwrap_or(word);
+    let mut chars = word.chars();
+    let symbol = chars.next()?;
+    let first = chars.next()?;
+    let valid = tagsymbols.contains(symbol)
+        && (first.is_alphanumeric() || first == '_')
+        && chars.all(|c| c.is_alphanumeric() || matches!(c, '_' | '-' | '+' | '/'));
+    valid.then_some(word)
 }
 
-#[derive(Debug, PartialEq)]
+/// Parse an entry time in `timeformat`, or failing that in one of the common
+/// formats.
+pub fn parse_time(time: &str, timeformat: &str) -> Option<NaiveDateTime> {
+    let time = time.trim();
+    std::iter::once(timeformat)
+        .chain(FALLBACK_TIMEFORMATS.iter().copied())
+        .find_map(|format| NaiveDateTime::parse_from_str(time, format).ok())
+}
+
+#[derive(Debug, Clone, PartialEq)]
 pub struct Entry {
     pub time: NaiveDateTime,
     title: String,
     text: String,
-    tags: Vec<String>,
     starred: bool,
 }
 
 impl Entry {
-    pub fn parse(raw_text: &[&str]) -> Result<Self> {
+    pub fn parse(raw_text: &[&str], timeformat: &str) -> Result<Self> {
         // println!("received entry chunk:\n{:?}", raw_text);
-        lazy_static! {
-            static ref TITLE_RE: Regex =
-                Regex::new(r"^[[:blank:]]*\[(?P<time>[^\]]+)\]\s*(?P<title>.*$)").unwrap();
-        }
         let time_title = raw_text
-            .get(0)
-            .ok_or(JrnlError(JrnlErrorKind::EmptyEntry))?;
+            .first()
+            .ok_or(JrnlError::new(JrnlErrorKind::EmptyEntry))?;
         let caps = TITLE_RE
             .captures(time_title)
-            .ok_or(JrnlError(JrnlErrorKind::InvalidTitleLine))?;
+            .ok_or(JrnlError::new(JrnlErrorKind::InvalidTitleLine))?;
         let time_str = &caps["time"].to_owned();
         let title = caps["title"].to_owned();
-        let starred = title.contains("*");
         let text = raw_text
             .iter()
             .skip(1)
@@ -81,23 +88,96 @@ impl Entry {
             .collect::<String>()
             .trim()
             .to_owned();
-        let tags = text
-            .split_whitespace()
-            // TODO: get tag chars from config
-            .filter(|word| word.starts_with(&['#', '@']))
-            .map(|word| word.to_owned())
-            .collect::<Vec<String>>();
         // println!("Parsing '{}' to datetime", time_str);
-        // TODO: get strftime fmt from config
-        let time = NaiveDateTime::parse_from_str(time_str, "%F %r")?.into();
+        let time = parse_time(time_str, timeformat)
+            .ok_or(JrnlError::new(JrnlErrorKind::InvalidTimestamp))?;
         // println!("parsed time: {:?}", time);
-        Ok(Entry {
+        Ok(Entry::new(time, &title, &text))
+    }
+
+    /// What's wrong with `line` as an entry header, if anything, along with the
+    /// byte range of the offending text.
+    pub fn header_problem(line: &str, timeformat: &str) -> Option<(JrnlErrorKind, Range<usize>)> {
+        let Some(caps) = TITLE_RE.captures(line) else {
+            let start = line.len() - line.trim_start().len();
+            let end = line.trim_end().len().max(start);
+            return Some((JrnlErrorKind::InvalidTitleLine, start..end));
+        };
+        let time = caps.name("time").expect("time is not optional");
+        parse_time(time.as_str(), timeformat)
+            .is_none()
+            .then(|| (JrnlErrorKind::InvalidTimestamp, time.range()))
+    }
+
+    pub fn new(time: NaiveDateTime, title: &str, text: &str) -> Self {
+        let starred = title.contains('*');
+        Entry {
             time,
-            title,
-            text,
-            tags,
+            title: title.to_owned(),
+            text: text.to_owned(),
             starred,
-        })
+        }
+    }
+
+    /// Build an entry from free-form text as typed on the command line or in
+    /// an editor. The first line or sentence becomes the title and the rest
+    /// the body.
+    pub fn compose(time: NaiveDateTime, raw: &str) -> Self {
+        let raw = raw.trim();
+        let split = title_split(raw);
+        let (title, text) = raw.split_at(split);
+        Entry::new(time, title.trim(), text.trim())
+    }
+
+    pub fn title(&self) -> &str {
+        &self.title
+    }
+
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    /// The words of the title and body that start with one of `tagsymbols`,
+    /// without trailing punctuation, each only once regardless of case.
+    pub fn tags(&self, tagsymbols: &str) -> Vec<String> {
+        let mut tags: Vec<String> = vec![];
+        let words = self
+            .title
+            .split_whitespace()
+            .chain(self.text.split_whitespace());
+        for tag in words.filter_map(|word| tag(word, tagsymbols)) {
+            if !tags.iter().any(|t| t.to_lowercase() == tag.to_lowercase()) {
+                tags.push(tag.to_owned());
+            }
+        }
+        tags
+    }
+
+    pub fn starred(&self) -> bool {
+        self.starred
+    }
+
+    /// Render the entry the way it is stored in a plain text journal.
+    pub fn format(&self, timeformat: &str) -> String {
+        let mut out = format!("[{}] {}\n", self.time.format(timeformat), self.title);
+        if !self.text.is_empty() {
+            out.push_str(&self.text);
+            out.push('\n');
+        }
+        out
+    }
+}
+
+/// Byte offset where the title ends: the first newline, or the end of the
+/// first sentence (terminator followed by whitespace or end of input).
+fn title_split(raw: &str) -> usize {
+    lazy_static! {
+        static ref SENTENCE_RE: Regex = Regex::new(r"[.?!]+(\s|$)").unwrap();
+    }
+    let first_line = raw.find('\n').unwrap_or(raw.len());
+    match SENTENCE_RE.find(&raw[..first_line]) {
+        Some(m) => m.end(),
+        None => first_line,
     }
 }
 
@@ -107,6 +187,7 @@ mod tests {
     use chrono::{NaiveDate, NaiveTime};
 
     #[test]
+    #[allow(clippy::zero_prefixed_literal)]
     fn test_parse() {
         let lines = [
             "[2023-01-12 08:51:57 AM] Test entry.",
@@ -119,10 +200,118 @@ mod tests {
                 time: NaiveDateTime::new(assert_date, assert_time),
                 title: "Test entry.".to_owned(),
                 text: "This is a test entry".to_owned(),
-                tags: vec![],
                 starred: false,
             },
-            Entry::parse(&lines).unwrap()
+            Entry::parse(&lines, "%F %r").unwrap()
+        );
+    }
+
+    #[test]
+    fn test_compose() {
+        let time = NaiveDate::from_ymd_opt(2023, 1, 12)
+            .unwrap()
+            .and_hms_opt(9, 0, 0)
+            .unwrap();
+        let entry = Entry::compose(time, "today was good. @work and #play");
+        assert_eq!(entry.title, "today was good.");
+        assert_eq!(entry.text, "@work and #play");
+        assert_eq!(entry.tags("#@"), ["@work", "#play"]);
+
+        let entry = Entry::compose(time, "Is it done?! Not yet");
+        assert_eq!(entry.title, "Is it done?!");
+        assert_eq!(entry.text, "Not yet");
+
+        let entry = Entry::compose(time, "no terminator here");
+        assert_eq!(entry.title, "no terminator here");
+        assert_eq!(entry.text, "");
+
+        let entry = Entry::compose(time, "version 1.2 shipped\nwith notes.");
+        assert_eq!(entry.title, "version 1.2 shipped");
+        assert_eq!(entry.text, "with notes.");
+    }
+
+    #[test]
+    fn test_format_round_trip() {
+        let time = NaiveDate::from_ymd_opt(2023, 1, 12)
+            .unwrap()
+            .and_hms_opt(17, 0, 0)
+            .unwrap();
+        let entry = Entry::compose(time, "Title here. Body with @tag");
+        let formatted = entry.format("%F %r");
+        assert_eq!(
+            formatted,
+            "[2023-01-12 05:00:00 PM] Title here.\nBody with @tag\n"
         );
+        let lines = formatted.lines().collect::<Vec<_>>();
+        assert_eq!(entry, Entry::parse(&lines, "%F %r").unwrap());
+    }
+
+    #[test]
+    fn test_parse_timeformat() {
+        let time = NaiveDate::from_ymd_opt(2023, 1, 12)
+            .unwrap()
+            .and_hms_opt(17, 5, 0)
+            .unwrap();
+        let entry = Entry::compose(time, "Title here.");
+        let formatted = entry.format("%d.%m.%Y %H:%M");
+        assert_eq!(formatted, "[12.01.2023 17:05] Title here.\n");
+        let lines = formatted.lines().collect::<Vec<_>>();
+        assert_eq!(entry, Entry::parse(&lines, "%d.%m.%Y %H:%M").unwrap());
+
+        // written with another format
+        let lines = ["[2023-01-12 05:05:00 PM] Title here."];
+        assert_eq!(entry, Entry::parse(&lines, "%d.%m.%Y %H:%M").unwrap());
+        let lines = ["[2023-01-12T17:05:00] Title here."];
+        assert_eq!(entry, Entry::parse(&lines, "%F %r").unwrap());
+
+        let lines = ["[someday] Title here."];
+        assert!(Entry::parse(&lines, "%F %r").is_err());
+    }
+
+    #[test]
+    fn test_tags() {
+        let time = NaiveDate::from_ymd_opt(2023, 1, 12)
+            .unwrap()
+            .and_hms_opt(9, 0, 0)
+            .unwrap();
+        let tags = |raw: &str, tagsymbols: &str| Entry::compose(time, raw).tags(tagsymbols);
+
+        // only the configured symbols
+        assert_eq!(tags("Met @alice. About #work", "@"), ["@alice"]);
+        assert_eq!(tags("Met @alice. About #work", "#"), ["#work"]);
+        assert_eq!(tags("Met +alice. About %work", "+%"), ["+alice", "%work"]);
+
+        // title tags come first
+        assert_eq!(
+            tags("Lunch with @bob. Then @carol", "@"),
+            ["@bob", "@carol"]
+        );
+
+        assert_eq!(
+            tags("Saw @alice, @bob! And (@carol). Was @dave's idea?", "@"),
+            ["@alice", "@bob", "@carol", "@dave"]
+        );
+        assert_eq!(
+            tags("Done. Tagged #home-office and #a_b.", "#"),
+            ["#home-office", "#a_b"]
+        );
+
+        assert_eq!(
+            tags("Mail. Wrote to bob@example.com about @work", "@"),
+            ["@work"]
+        );
+        assert_eq!(
+            tags(
+                "Link. See https://example.com/page#section and #/route",
+                "#"
+            ),
+            Vec::<String>::new()
+        );
+        assert_eq!(
+            tags("Lone. Just # and @ and #!", "#@"),
+            Vec::<String>::new()
+        );
+
+        assert_eq!(tags("@Work. More @work and @WORK", "@"), ["@Work"]);
     }
 }
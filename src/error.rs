@@ -1,15 +1,73 @@
 use std::fmt;
+use std::ops::Range;
+use std::path::{Path, PathBuf};
 
 // pub type Result<T> = std::result::Result<T, Box<dyn std::error::Error>>;
 pub type Result<T> = std::result::Result<T, JrnlError>;
 
+/// Exit status for errors that don't fall in any of the groups below, such as
+/// a wrong password.
+pub const EXIT_FAILURE: u8 = 1;
+/// Exit status for invalid command line arguments, as used by clap.
+pub const EXIT_USAGE: u8 = 2;
+/// Exit status for a missing or invalid config file or config override.
+pub const EXIT_CONFIG: u8 = 3;
+/// Exit status for a journal or import source that can't be parsed.
+pub const EXIT_PARSE: u8 = 4;
+/// Exit status for failing to read or write a file.
+pub const EXIT_IO: u8 = 5;
+/// Exit status for when the user gave up, e.g. at a prompt.
+pub const EXIT_ABORTED: u8 = 6;
+
 #[derive(Debug, Copy, Clone, Eq, PartialEq)]
 pub enum JrnlErrorKind {
     EmptyEntry,
     InvalidTitleLine,
+    InvalidTimestamp,
+    InvalidJournal,
+    InvalidImport,
     MissingJournalConfig,
     TopLevelJournalConfig,
     InvalidJrnlOverrideConfig,
+    InvalidConfig,
+    UnsupportedEncryption,
+    #[cfg_attr(feature = "sqlite", allow(dead_code))]
+    NoSqliteSupport,
+    InvalidConfigOverride,
+    NoHomeDirectory,
+    InvalidEncryptedJournal,
+    UnsupportedEncryptionVersion,
+    WrongPassword,
+    InvalidArgument,
+    Io,
+    Aborted,
+}
+
+impl JrnlErrorKind {
+    /// The exit status of jrnl when failing with an error of this kind.
+    pub fn exit_code(self) -> u8 {
+        match self {
+            Self::EmptyEntry
+            | Self::InvalidTitleLine
+            | Self::InvalidTimestamp
+            | Self::InvalidJournal
+            | Self::InvalidImport => EXIT_PARSE,
+            Self::MissingJournalConfig
+            | Self::TopLevelJournalConfig
+            | Self::InvalidJrnlOverrideConfig
+            | Self::InvalidConfig
+            | Self::InvalidConfigOverride
+            | Self::UnsupportedEncryption
+            | Self::NoSqliteSupport
+            | Self::NoHomeDirectory => EXIT_CONFIG,
+            Self::InvalidEncryptedJournal
+            | Self::UnsupportedEncryptionVersion
+            | Self::WrongPassword => EXIT_FAILURE,
+            Self::InvalidArgument => EXIT_USAGE,
+            Self::Io => EXIT_IO,
+            Self::Aborted => EXIT_ABORTED,
+        }
+    }
 }
 
 impl fmt::Display for JrnlErrorKind {
@@ -17,30 +75,248 @@ impl fmt::Display for JrnlErrorKind {
         let msg = match self {
             Self::EmptyEntry => "entry is empty",
             Self::InvalidTitleLine => "failed to parse entry title",
+            Self::InvalidTimestamp => "failed to parse entry time",
+            Self::InvalidJournal => "journal has entries that can't be parsed",
+            Self::InvalidImport => "not a valid import source",
             Self::MissingJournalConfig => "no such journal configured",
             Self::TopLevelJournalConfig => "illegal 'journal' key found at top level",
             Self::InvalidJrnlOverrideConfig => {
                 "journal-specific config specifies multiple journals"
             }
+            Self::InvalidConfig => "invalid config file",
+            Self::InvalidConfigOverride => "invalid config override",
+            Self::UnsupportedEncryption => "this kind of journal can't be encrypted",
+            Self::NoSqliteSupport => "jrnl was built without SQLite support",
+            Self::NoHomeDirectory => "could not find the home directory",
+            Self::InvalidEncryptedJournal => "journal file is not a valid encrypted journal",
+            Self::UnsupportedEncryptionVersion => {
+                "journal was encrypted by a newer version of jrnl"
+            }
+            Self::WrongPassword => "wrong password",
+            Self::InvalidArgument => "invalid argument",
+            Self::Io => "input/output error",
+            Self::Aborted => "aborted",
         };
         write!(f, "{msg}")
     }
 }
 
+/// An error of some kind, with what jrnl was doing when it happened and the
+/// error that caused it, if any.
 #[derive(Debug)]
-#[allow(dead_code)]
-pub struct JrnlError(pub JrnlErrorKind);
+pub struct JrnlError {
+    kind: JrnlErrorKind,
+    context: Option<String>,
+    source: Option<Box<dyn std::error::Error + Send + Sync>>,
+}
 
 impl fmt::Display for JrnlError {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "{}", self.kind())
+        match &self.context {
+            Some(context) => write!(f, "{context}: {}", self.kind),
+            None => write!(f, "{}", self.kind),
+        }
     }
 }
 
 impl JrnlError {
+    pub fn new(kind: JrnlErrorKind) -> Self {
+        Self {
+            kind,
+            context: None,
+            source: None,
+        }
+    }
+
+    /// Say what was being done, e.g. "failed to read '<path>'".
+    pub fn context(mut self, context: impl Into<String>) -> Self {
+        self.context = Some(context.into());
+        self
+    }
+
+    pub fn source(mut self, source: impl Into<Box<dyn std::error::Error + Send + Sync>>) -> Self {
+        self.source = Some(source.into());
+        self
+    }
+
     pub fn kind(&self) -> JrnlErrorKind {
-        self.0
+        self.kind
     }
 }
 
-impl std::error::Error for JrnlError {}
+impl From<JrnlErrorKind> for JrnlError {
+    fn from(kind: JrnlErrorKind) -> Self {
+        Self::new(kind)
+    }
+}
+
+impl std::error::Error for JrnlError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        self.source
+            .as_deref()
+            .map(|source| source as &(dyn std::error::Error + 'static))
+    }
+}
+
+/// The kind of the first error in the chain of `err` and its sources that
+/// jrnl knows a kind for.
+pub fn kind_of(err: &(dyn std::error::Error + 'static)) -> Option<JrnlErrorKind> {
+    let mut next = Some(err);
+    while let Some(err) = next {
+        if let Some(err) = err.downcast_ref::<JrnlError>() {
+            return Some(err.kind());
+        }
+        if let Some(err) = err.downcast_ref::<ParseError>() {
+            return Some(err.kind);
+        }
+        if err.is::<std::io::Error>() {
+            return Some(JrnlErrorKind::Io);
+        }
+        if let Some(err) = err.downcast_ref::<config::ConfigError>() {
+            return match err {
+                config::ConfigError::Foreign(err) => kind_of(err.as_ref()),
+                _ => Some(JrnlErrorKind::InvalidConfig),
+            };
+        }
+        next = err.source();
+    }
+    None
+}
+
+/// The exit statuses of jrnl and what they mean, as listed by `--help`.
+pub fn exit_codes_help() -> String {
+    let codes = [
+        (0, "success"),
+        (EXIT_FAILURE, "other errors, such as a wrong password"),
+        (EXIT_USAGE, "invalid arguments"),
+        (EXIT_CONFIG, "missing, invalid or unsupported config"),
+        (EXIT_PARSE, "journal or import source can't be parsed"),
+        (EXIT_IO, "failed to read or write a file"),
+        (EXIT_ABORTED, "aborted, e.g. at a prompt"),
+    ];
+    let lines = codes.map(|(code, meaning)| format!("  {code}  {meaning}"));
+    format!("Exit codes:\n{}", lines.join("\n"))
+}
+
+/// The exit status for failing with `err`.
+pub fn exit_code(err: &(dyn std::error::Error + 'static)) -> u8 {
+    kind_of(err).map_or(EXIT_FAILURE, JrnlErrorKind::exit_code)
+}
+
+/// `err` and its chain of sources as shown to the user.
+pub fn report(err: &(dyn std::error::Error + 'static)) -> String {
+    let mut out = match err.is::<ParseError>() {
+        true => err.to_string(),
+        false => format!("Error: {err}"),
+    };
+    let mut source = err.source();
+    while let Some(err) = source {
+        // some errors already include their source in their message
+        if !out.ends_with(&err.to_string()) {
+            out.push_str(&format!("\n  caused by: {err}"));
+        }
+        source = err.source();
+    }
+    out
+}
+
+/// A problem at a particular line of a journal, shown compiler-style with a
+/// caret under the offending text.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParseError {
+    pub kind: JrnlErrorKind,
+    pub journal: String,
+    pub path: Option<PathBuf>,
+    /// 1-based.
+    pub line: usize,
+    pub text: String,
+    /// Byte range of the offending part of `text`.
+    pub span: Range<usize>,
+    pub note: Option<String>,
+}
+
+impl ParseError {
+    pub fn in_file(mut self, path: &Path) -> Self {
+        self.path = Some(path.to_owned());
+        self
+    }
+
+    /// The problem as an error or warning, depending on `level`.
+    pub fn render(&self, level: &str) -> String {
+        let location = match &self.path {
+            Some(path) => format!("{}:{}", path.display(), self.line),
+            None => format!("line {}", self.line),
+        };
+        let number = self.line.to_string();
+        let gutter = " ".repeat(number.len());
+        let start = self.text.get(..self.span.start).unwrap_or_default();
+        let marked = self.text.get(self.span.clone()).unwrap_or_default();
+        let caret = format!(
+            "{}{}",
+            " ".repeat(start.chars().count()),
+            "^".repeat(marked.chars().count().max(1))
+        );
+        let mut out = format!(
+            "{level}: {}\n{gutter}--> {location} (journal '{}')\n{gutter} |\n{number} | {}\n{gutter} | {caret}",
+            self.kind, self.journal, self.text
+        );
+        if let Some(note) = &self.note {
+            out.push_str(&format!("\n{gutter} = note: {note}"));
+        }
+        out
+    }
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.render("error"))
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_exit_code_and_report() {
+        let io = std::io::Error::new(std::io::ErrorKind::NotFound, "no such file");
+        let err = JrnlError::new(JrnlErrorKind::InvalidImport)
+            .context("failed to read 'x.json'")
+            .source(io);
+        assert_eq!(exit_code(&err), EXIT_PARSE);
+        assert_eq!(
+            report(&err),
+            "Error: failed to read 'x.json': not a valid import source\n  caused by: no such file"
+        );
+
+        // going by the first known kind in the chain
+        let boxed: Box<dyn std::error::Error> = "plain".into();
+        assert_eq!(exit_code(boxed.as_ref()), EXIT_FAILURE);
+        let io = std::io::Error::other(JrnlError::new(JrnlErrorKind::Aborted));
+        assert_eq!(exit_code(&io), EXIT_IO);
+        let config = config::ConfigError::Foreign(Box::new(JrnlError::new(
+            JrnlErrorKind::InvalidConfigOverride,
+        )));
+        assert_eq!(exit_code(&config), EXIT_CONFIG);
+    }
+
+    #[test]
+    fn test_exit_codes_help() {
+        let help = exit_codes_help();
+        assert!(help.starts_with("Exit codes:\n  0  success\n"));
+        for code in [
+            EXIT_FAILURE,
+            EXIT_USAGE,
+            EXIT_CONFIG,
+            EXIT_PARSE,
+            EXIT_IO,
+            EXIT_ABORTED,
+        ] {
+            assert!(help.contains(&format!("\n  {code}  ")), "{code}");
+        }
+        let usage = JrnlError::new(JrnlErrorKind::InvalidArgument);
+        assert_eq!(exit_code(&usage), EXIT_USAGE);
+    }
+}
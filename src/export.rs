@@ -0,0 +1,163 @@
+use lazy_static::lazy_static;
+use regex::Regex;
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::entry::Entry;
+use crate::format::Formatter;
+use crate::journal::write_atomic;
+
+type Result<T> = std::result::Result<T, Box<dyn std::error::Error>>;
+
+/// Write `entries` to `path`. If `path` is a directory (or ends with a path
+/// separator) and the format supports it, each entry goes to its own file
+/// named after its date and title.
+pub fn export(formatter: &dyn Formatter, entries: &[&Entry], path: &str) -> Result<()> {
+    let is_dir = path.ends_with(std::path::is_separator) || Path::new(path).is_dir();
+    match (is_dir, formatter.extension()) {
+        (true, Some(extension)) => {
+            fs::create_dir_all(path)?;
+            let mut taken = HashSet::new();
+            for entry in entries {
+                let file = unique_filename(Path::new(path), entry, extension, &mut taken);
+                write_atomic(&file, formatter.format_entry(entry).as_bytes())?;
+            }
+            eprintln!("[{} entries exported to directory {path}]", entries.len());
+        }
+        (true, None) => {
+            return Err(format!("this format can't be exported to a directory: {path}").into())
+        }
+        (false, _) => {
+            write_atomic(
+                Path::new(path),
+                formatter.format_entries(entries).as_bytes(),
+            )?;
+            eprintln!("[Journal exported to {path}]");
+        }
+    }
+    Ok(())
+}
+
+/// `YYYY-MM-DD_slugified-title.ext`, with a counter appended if several
+/// entries on the same day share a title or the file already exists, so that
+/// nothing in `dir` is overwritten.
+fn unique_filename(
+    dir: &Path,
+    entry: &Entry,
+    extension: &str,
+    taken: &mut HashSet<PathBuf>,
+) -> PathBuf {
+    let stem = format!("{}_{}", entry.time.format("%F"), slugify(entry.title()));
+    let mut file = dir.join(format!("{stem}.{extension}"));
+    let mut n = 1;
+    while file.exists() || !taken.insert(file.clone()) {
+        n += 1;
+        file = dir.join(format!("{stem}-{n}.{extension}"));
+    }
+    file
+}
+
+fn slugify(text: &str) -> String {
+    lazy_static! {
+        static ref PUNCTUATION_RE: Regex = Regex::new(r"[^\w\s-]").unwrap();
+        static ref SEPARATOR_RE: Regex = Regex::new(r"[-\s]+").unwrap();
+    }
+    let text = PUNCTUATION_RE.replace_all(text, "");
+    let text = text.trim().to_lowercase();
+    SEPARATOR_RE.replace_all(&text, "-").into_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::format::{formatter, FormatOptions};
+    use crate::journal::Journal;
+    use crate::settings::DisplayConfig;
+
+    const JOURNAL: &str = "[2023-01-12 08:00:00 AM] Hello, world!
+Body.
+
+[2023-01-12 09:00:00 AM] Hello, world!
+
+[2023-01-13 09:00:00 AM] Another day.
+";
+
+    fn options() -> FormatOptions {
+        FormatOptions::for_test("%F %R")
+    }
+
+    #[test]
+    fn test_slugify() {
+        assert_eq!(slugify("Hello, world!"), "hello-world");
+        assert_eq!(slugify("  Ünïcode -- and   spaces "), "ünïcode-and-spaces");
+    }
+
+    #[test]
+    fn test_export_directory() {
+        let dir = tempfile::tempdir().unwrap();
+        let journal = Journal::parse("default", JOURNAL, "%F %r").unwrap();
+        let entries = journal.entries().iter().collect::<Vec<_>>();
+        let formatter = formatter(DisplayConfig::Markdown, options());
+        let path = format!("{}/", dir.path().join("site").display());
+        export(formatter.as_ref(), &entries, &path).unwrap();
+
+        let mut files = fs::read_dir(dir.path().join("site"))
+            .unwrap()
+            .map(|file| file.unwrap().file_name().into_string().unwrap())
+            .collect::<Vec<_>>();
+        files.sort();
+        assert_eq!(
+            files,
+            [
+                "2023-01-12_hello-world-2.md",
+                "2023-01-12_hello-world.md",
+                "2023-01-13_another-day.md"
+            ]
+        );
+        let first = fs::read_to_string(dir.path().join("site/2023-01-12_hello-world.md")).unwrap();
+        assert_eq!(first, "# 2023-01-12 08:00 Hello, world!\n\nBody.\n");
+    }
+
+    #[test]
+    fn test_export_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let journal = Journal::parse("default", JOURNAL, "%F %r").unwrap();
+        let entries = journal.entries().iter().collect::<Vec<_>>();
+        let path = dir.path().join("journal.json");
+        let formatter = formatter(DisplayConfig::Json, options());
+        export(formatter.as_ref(), &entries, path.to_str().unwrap()).unwrap();
+        let written = fs::read_to_string(&path).unwrap();
+        assert_eq!(written, formatter.format_entries(&entries));
+
+        assert!(export(formatter.as_ref(), &entries, dir.path().to_str().unwrap()).is_err());
+    }
+
+    #[test]
+    fn test_export_directory_twice() {
+        let dir = tempfile::tempdir().unwrap();
+        let journal = Journal::parse("default", JOURNAL, "%F %r").unwrap();
+        let entries = journal.entries().iter().collect::<Vec<_>>();
+        let formatter = formatter(DisplayConfig::Markdown, options());
+        let path = dir.path().to_str().unwrap();
+        export(formatter.as_ref(), &entries[..1], path).unwrap();
+        export(formatter.as_ref(), &entries, path).unwrap();
+
+        let mut files = fs::read_dir(dir.path())
+            .unwrap()
+            .map(|file| file.unwrap().file_name().into_string().unwrap())
+            .collect::<Vec<_>>();
+        files.sort();
+        assert_eq!(
+            files,
+            [
+                "2023-01-12_hello-world-2.md",
+                "2023-01-12_hello-world-3.md",
+                "2023-01-12_hello-world.md",
+                "2023-01-13_another-day.md"
+            ]
+        );
+        let first = fs::read_to_string(dir.path().join("2023-01-12_hello-world.md")).unwrap();
+        assert_eq!(first, "# 2023-01-12 08:00 Hello, world!\n\nBody.\n");
+    }
+}
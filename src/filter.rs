@@ -0,0 +1,346 @@
+use chrono::{Datelike, Month, NaiveDate, NaiveDateTime, NaiveTime};
+
+use crate::cli::SearchOptions;
+use crate::datetime;
+use crate::entry::Entry;
+use crate::error::{JrnlError, JrnlErrorKind};
+use crate::journal::Journal;
+
+type Result<T> = std::result::Result<T, Box<dyn std::error::Error>>;
+
+type Predicate = Box<dyn Fn(&Entry) -> bool>;
+type DatePredicate = Box<dyn Fn(NaiveDate) -> bool>;
+
+/// A set of search criteria built from `SearchOptions` and any tags given on
+/// the command line.
+///
+/// As in jrnl, tags and `--contains` match if any of them match, or only if all
+/// of them match with `--and`. Every other criterion always has to hold.
+pub struct Filter {
+    predicates: Vec<Predicate>,
+    /// Criteria on the date alone, which storage can check before reading
+    /// entries.
+    date_predicates: Vec<DatePredicate>,
+    limit: Option<usize>,
+}
+
+impl Filter {
+    pub fn new(
+        options: &SearchOptions,
+        tags: &[String],
+        tagsymbols: &str,
+        now: NaiveDateTime,
+        default_time: NaiveTime,
+    ) -> Result<Self> {
+        let strict = options.and == Some(true);
+        let mut filter = Self {
+            predicates: vec![],
+            date_predicates: vec![],
+            limit: options.n.map(|n| n as usize),
+        };
+
+        let mut terms: Vec<Predicate> = tags.iter().map(|tag| has_tag(tag, tagsymbols)).collect();
+        if let Some(text) = &options.contains {
+            let text = text.to_lowercase();
+            terms.push(Box::new(move |entry| {
+                entry.title().to_lowercase().contains(&text)
+                    || entry.text().to_lowercase().contains(&text)
+            }));
+        }
+        if !terms.is_empty() {
+            filter.push(if strict { all(terms) } else { any(terms) });
+        }
+
+        let (mut starred, mut tagged) =
+            (options.starred == Some(true), options.tagged == Some(true));
+        match options.not.as_deref() {
+            Some("--starred" | "starred") => {
+                starred = false;
+                filter.push(Box::new(|entry| !entry.starred()));
+            }
+            Some("--tagged" | "tagged") => {
+                tagged = false;
+                let tagsymbols = tagsymbols.to_owned();
+                filter.push(Box::new(move |entry| entry.tags(&tagsymbols).is_empty()));
+            }
+            Some(tag) => {
+                let excluded = has_tag(tag, tagsymbols);
+                filter.push(Box::new(move |entry| !excluded(entry)));
+            }
+            None => (),
+        }
+        if starred {
+            filter.push(Box::new(|entry| entry.starred()));
+        }
+        if tagged {
+            let tagsymbols = tagsymbols.to_owned();
+            filter.push(Box::new(move |entry| !entry.tags(&tagsymbols).is_empty()));
+        }
+
+        let parse_date = |input: &str| -> Result<NaiveDate> {
+            datetime::parse(input, now, default_time)
+                .map(|time| time.date())
+                .ok_or_else(|| invalid(format!("could not parse date '{input}'")).into())
+        };
+        if let Some(on) = &options.on {
+            let on = parse_date(on)?;
+            filter.push_date(Box::new(move |date| date == on));
+        }
+        if let Some(from) = &options.from {
+            let from = parse_date(from)?;
+            filter.push_date(Box::new(move |date| date >= from));
+        }
+        if let Some(to) = &options.to {
+            let to = parse_date(to)?;
+            filter.push_date(Box::new(move |date| date <= to));
+        }
+        if let Some(year) = &options.year {
+            let year = year
+                .trim()
+                .parse::<i32>()
+                .map_err(|_| invalid(format!("invalid year '{year}'")))?;
+            filter.push_date(Box::new(move |date| date.year() == year));
+        }
+        if let Some(month) = &options.month {
+            let month =
+                parse_month(month).ok_or_else(|| invalid(format!("invalid month '{month}'")))?;
+            filter.push_date(Box::new(move |date| date.month() == month));
+        }
+        if let Some(day) = &options.day {
+            let day = day
+                .trim()
+                .parse::<u32>()
+                .ok()
+                .filter(|day| (1..=31).contains(day))
+                .ok_or_else(|| invalid(format!("invalid day '{day}'")))?;
+            filter.push_date(Box::new(move |date| date.day() == day));
+        }
+        if options.today_in_history == Some(true) {
+            let (month, day) = (now.month(), now.day());
+            filter.push_date(Box::new(move |date| {
+                date.month() == month && date.day() == day
+            }));
+        }
+
+        Ok(filter)
+    }
+
+    fn push(&mut self, predicate: Predicate) {
+        self.predicates.push(predicate);
+    }
+
+    fn push_date(&mut self, predicate: DatePredicate) {
+        self.date_predicates.push(predicate);
+    }
+
+    /// Whether entries on `date` can match at all.
+    pub fn matches_date(&self, date: NaiveDate) -> bool {
+        self.date_predicates.iter().all(|predicate| predicate(date))
+    }
+
+    pub fn matches(&self, entry: &Entry) -> bool {
+        self.matches_date(entry.time.date())
+            && self.predicates.iter().all(|predicate| predicate(entry))
+    }
+
+    /// Matching entries of `journal` in chronological order. With `-n`, only
+    /// the most recent matches are kept.
+    pub fn apply<'a>(&self, journal: &'a Journal) -> Vec<&'a Entry> {
+        let mut matches = journal
+            .entries()
+            .iter()
+            .filter(|entry| self.matches(entry))
+            .collect::<Vec<_>>();
+        matches.sort_by_key(|entry| entry.time);
+        if let Some(limit) = self.limit {
+            matches.drain(..matches.len().saturating_sub(limit));
+        }
+        matches
+    }
+}
+
+/// The words in `words` that are tags, i.e. start with one of `tagsymbols`.
+pub fn tags_in(words: &[String], tagsymbols: &str) -> Vec<String> {
+    words
+        .iter()
+        .flat_map(|word| word.split_whitespace())
+        .filter(|word| is_tag(word, tagsymbols))
+        .map(|word| word.to_owned())
+        .collect()
+}
+
+/// Whether every word in `words` is a tag, in which case jrnl searches for
+/// those tags rather than composing an entry.
+pub fn all_tags(words: &[String], tagsymbols: &str) -> bool {
+    let mut words = words
+        .iter()
+        .flat_map(|word| word.split_whitespace())
+        .peekable();
+    words.peek().is_some() && words.all(|word| is_tag(word, tagsymbols))
+}
+
+fn is_tag(word: &str, tagsymbols: &str) -> bool {
+    word.len() > 1 && word.starts_with(|c| tagsymbols.contains(c))
+}
+
+fn has_tag(tag: &str, tagsymbols: &str) -> Predicate {
+    let tag = tag.to_lowercase();
+    let tagsymbols = tagsymbols.to_owned();
+    Box::new(move |entry| {
+        entry
+            .tags(&tagsymbols)
+            .iter()
+            .any(|t| t.to_lowercase() == tag)
+    })
+}
+
+fn all(predicates: Vec<Predicate>) -> Predicate {
+    Box::new(move |entry| predicates.iter().all(|predicate| predicate(entry)))
+}
+
+fn any(predicates: Vec<Predicate>) -> Predicate {
+    Box::new(move |entry| predicates.iter().any(|predicate| predicate(entry)))
+}
+
+/// An error for a search option given a value that makes no sense.
+fn invalid(problem: String) -> JrnlError {
+    JrnlError::new(JrnlErrorKind::InvalidArgument).context(problem)
+}
+
+fn parse_month(month: &str) -> Option<u32> {
+    let month = month.trim();
+    match month.parse::<u32>() {
+        Ok(number) if (1..=12).contains(&number) => Some(number),
+        Ok(_) => None,
+        Err(_) => month.parse::<Month>().ok().map(|m| m.number_from_month()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cli::Cli;
+    use clap::Parser;
+
+    const JOURNAL: &str = "[2021-03-05 09:00:00 AM] Old entry.
+Nothing to see @work
+
+[2023-01-12 08:00:00 AM] Starred *
+With #home and @work
+
+[2022-01-12 10:00:00 PM] A year ago.
+Talked to @alice about the garden
+
+[2023-01-11 07:30:00 PM] Yesterday.
+Plain text
+";
+
+    fn journal() -> Journal {
+        Journal::parse("default", JOURNAL, "%F %r").unwrap()
+    }
+
+    fn now() -> NaiveDateTime {
+        NaiveDate::from_ymd_opt(2023, 1, 12)
+            .unwrap()
+            .and_hms_opt(14, 30, 0)
+            .unwrap()
+    }
+
+    fn search(args: &[&str]) -> Vec<String> {
+        let cli = Cli::parse_from(["jrnl"].iter().chain(args));
+        let tags = tags_in(&cli.entry, "#@");
+        let journal = journal();
+        let filter = Filter::new(
+            &cli.search,
+            &tags,
+            "#@",
+            now(),
+            NaiveTime::from_hms_opt(9, 0, 0).unwrap(),
+        )
+        .unwrap();
+        filter
+            .apply(&journal)
+            .iter()
+            .map(|entry| entry.title().to_owned())
+            .collect()
+    }
+
+    #[test]
+    fn test_no_filters_sorts() {
+        assert_eq!(
+            search(&["--n", "10"]),
+            ["Old entry.", "A year ago.", "Yesterday.", "Starred *"]
+        );
+        assert_eq!(search(&["--n", "2"]), ["Yesterday.", "Starred *"]);
+    }
+
+    #[test]
+    fn test_tags() {
+        assert_eq!(search(&["default", "@work"]), ["Old entry.", "Starred *"]);
+        assert_eq!(
+            search(&["default", "@WORK", "@alice"]),
+            ["Old entry.", "A year ago.", "Starred *"]
+        );
+        assert_eq!(
+            search(&["default", "@work", "#home", "--and"]),
+            ["Starred *"]
+        );
+        assert_eq!(
+            search(&["default", "@alice", "--contains", "plain"]),
+            ["A year ago.", "Yesterday."]
+        );
+    }
+
+    #[test]
+    fn test_flags() {
+        assert_eq!(search(&["--starred"]), ["Starred *"]);
+        assert_eq!(
+            search(&["--tagged"]),
+            ["Old entry.", "A year ago.", "Starred *"]
+        );
+        assert_eq!(
+            search(&["--not", "--starred"]),
+            ["Old entry.", "A year ago.", "Yesterday."]
+        );
+        assert_eq!(search(&["--not", "--tagged"]), ["Yesterday."]);
+        assert_eq!(search(&["--not", "@work", "--tagged"]), ["A year ago."]);
+    }
+
+    #[test]
+    fn test_dates() {
+        assert_eq!(search(&["--on", "yesterday"]), ["Yesterday."]);
+        assert_eq!(search(&["--from", "2022-01-01"]).len(), 3);
+        assert_eq!(
+            search(&["--to", "2022-01-12"]),
+            ["Old entry.", "A year ago."]
+        );
+        assert_eq!(
+            search(&["--from", "2022-01-01", "--to", "yesterday"]),
+            ["A year ago.", "Yesterday."]
+        );
+        assert_eq!(search(&["--year", "2021"]), ["Old entry."]);
+        assert_eq!(search(&["--month", "march"]), ["Old entry."]);
+        assert_eq!(search(&["--month", "1", "--day", "11"]), ["Yesterday."]);
+        assert_eq!(
+            search(&["--today-in-history"]),
+            ["A year ago.", "Starred *"]
+        );
+    }
+
+    #[test]
+    fn test_matches_date() {
+        let cli = Cli::parse_from(["jrnl", "--from", "2022-01-01", "--month", "1", "--starred"]);
+        let filter = Filter::new(
+            &cli.search,
+            &[],
+            "#@",
+            now(),
+            NaiveTime::from_hms_opt(9, 0, 0).unwrap(),
+        )
+        .unwrap();
+        let date = |y, m, d| NaiveDate::from_ymd_opt(y, m, d).unwrap();
+        assert!(filter.matches_date(date(2022, 1, 12)));
+        assert!(!filter.matches_date(date(2021, 1, 12)));
+        assert!(!filter.matches_date(date(2022, 2, 12)));
+    }
+}
@@ -0,0 +1,437 @@
+use indexmap::IndexMap;
+use lazy_static::lazy_static;
+use regex::Regex;
+use std::io::Write;
+use termcolor::{Buffer, ColorSpec, WriteColor};
+
+use crate::entry::Entry;
+use crate::settings::{ColorConfig, DisplayConfig, LineWrapConfig, Settings, TextColor};
+
+mod boxed;
+mod dates;
+mod json;
+mod markdown;
+mod pretty;
+mod short;
+mod tags;
+mod text;
+mod xml;
+mod yaml;
+
+type Result<T> = std::result::Result<T, Box<dyn std::error::Error>>;
+
+/// Renders a selection of entries in one of the `DisplayConfig` formats.
+pub trait Formatter {
+    fn format_entries(&self, entries: &[&Entry]) -> String;
+
+    /// File extension used when exporting one file per entry, for the formats
+    /// that support it.
+    fn extension(&self) -> Option<&'static str> {
+        None
+    }
+
+    /// A single entry as written to its own file.
+    fn format_entry(&self, entry: &Entry) -> String {
+        self.format_entries(&[entry])
+    }
+}
+
+/// Journal settings that affect how entries are rendered.
+#[derive(Debug, Clone)]
+pub struct FormatOptions {
+    pub timeformat: String,
+    pub tagsymbols: String,
+    pub linewrap: Option<usize>,
+    pub indent_character: char,
+    pub colors: Option<ColorConfig>,
+    pub highlight: bool,
+}
+
+impl FormatOptions {
+    /// Options for `journal_name`. Colors are only used when `color` is set,
+    /// i.e. when writing to a terminal.
+    pub fn from_settings(settings: &Settings, journal_name: &str, color: bool) -> Result<Self> {
+        let linewrap = match settings.linewrap(journal_name)? {
+            LineWrapConfig::Auto => std::env::var("COLUMNS")
+                .ok()
+                .and_then(|columns| columns.parse().ok())
+                .or(Some(79)),
+            LineWrapConfig::Columns(columns) => usize::try_from(columns).ok(),
+        }
+        .filter(|&columns| columns > 0);
+        Ok(Self {
+            timeformat: settings.timeformat(journal_name)?,
+            tagsymbols: settings.tagsymbols(journal_name)?,
+            linewrap,
+            indent_character: settings.indent_character(journal_name)?,
+            colors: color.then(|| settings.colors(journal_name)).transpose()?,
+            highlight: settings.highlight(journal_name)?,
+        })
+    }
+}
+
+#[cfg(test)]
+impl FormatOptions {
+    /// Plain options for tests: entry times in `timeformat`, the default tag
+    /// symbols and no wrapping, colors or highlighting.
+    pub fn for_test(timeformat: &str) -> Self {
+        Self {
+            timeformat: timeformat.to_owned(),
+            tagsymbols: "#@".to_owned(),
+            linewrap: None,
+            indent_character: '|',
+            colors: None,
+            highlight: false,
+        }
+    }
+}
+
+pub fn formatter(display: DisplayConfig, options: FormatOptions) -> Box<dyn Formatter> {
+    match display {
+        DisplayConfig::Boxed => Box::new(boxed::Boxed(options)),
+        DisplayConfig::Dates => Box::new(dates::Dates),
+        DisplayConfig::Json => Box::new(json::Json(options)),
+        DisplayConfig::Markdown => Box::new(markdown::Markdown(options)),
+        DisplayConfig::Pretty => Box::new(pretty::Pretty(options)),
+        DisplayConfig::Short => Box::new(short::Short(options)),
+        DisplayConfig::Tags => Box::new(tags::Tags(options)),
+        DisplayConfig::Text => Box::new(text::Text(options)),
+        DisplayConfig::Xml => Box::new(xml::Xml(options)),
+        DisplayConfig::Yaml => Box::new(yaml::Yaml(options)),
+    }
+}
+
+/// Number of entries each tag appears in, most frequent first.
+fn tag_counts(entries: &[&Entry], tagsymbols: &str) -> IndexMap<String, usize> {
+    let mut counts = IndexMap::<String, usize>::new();
+    for entry in entries {
+        for tag in entry.tags(tagsymbols) {
+            *counts.entry(tag).or_default() += 1;
+        }
+    }
+    counts.sort_by(|tag_a, count_a, tag_b, count_b| {
+        count_b.cmp(count_a).then_with(|| tag_a.cmp(tag_b))
+    });
+    counts
+}
+
+/// Greedy word wrap in the manner of Python's `textwrap.wrap`, which jrnl's
+/// exporters are built on. Words longer than a line are broken.
+fn wrap(text: &str, width: usize, initial_indent: &str, subsequent_indent: &str) -> Vec<String> {
+    let mut lines = vec![];
+    let mut line = String::new();
+    let mut line_len = 0;
+    let mut words = text
+        .split_whitespace()
+        .map(str::to_owned)
+        .collect::<Vec<_>>();
+    words.reverse();
+    while let Some(word) = words.pop() {
+        let indent = match lines.is_empty() {
+            true => initial_indent,
+            false => subsequent_indent,
+        };
+        if line.is_empty() {
+            line.push_str(indent);
+            line_len = indent.chars().count();
+        }
+        let word_len = word.chars().count();
+        let fresh = line_len == indent.chars().count();
+        if fresh && line_len + word_len > width {
+            let room = width.saturating_sub(line_len).max(1);
+            let (head, tail) = word.split_at(
+                word.char_indices()
+                    .nth(room)
+                    .map(|(i, _)| i)
+                    .unwrap_or(word.len()),
+            );
+            line.push_str(head);
+            lines.push(std::mem::take(&mut line));
+            if !tail.is_empty() {
+                words.push(tail.to_owned());
+            }
+        } else if fresh {
+            line.push_str(&word);
+            line_len += word_len;
+        } else if line_len + 1 + word_len <= width {
+            line.push(' ');
+            line.push_str(&word);
+            line_len += 1 + word_len;
+        } else {
+            lines.push(std::mem::take(&mut line));
+            words.push(word);
+        }
+    }
+    if !line.is_empty() {
+        lines.push(line);
+    }
+    lines
+}
+
+/// Pad `text` with spaces to `width` characters, like Python's `str.ljust`.
+fn ljust(text: &str, width: usize) -> String {
+    let len = text.chars().count();
+    format!("{text}{}", " ".repeat(width.saturating_sub(len)))
+}
+
+/// Applies the configured colors, or leaves text alone when colors are off.
+struct Painter {
+    colors: Option<ColorConfig>,
+    tags: Vec<String>,
+}
+
+impl Painter {
+    fn new(options: &FormatOptions, entry: &Entry) -> Self {
+        let tags = match options.highlight {
+            true => entry.tags(&options.tagsymbols),
+            false => vec![],
+        };
+        Self {
+            colors: options.colors,
+            tags,
+        }
+    }
+
+    fn paint(&self, text: &str, color: impl Fn(&ColorConfig) -> TextColor) -> String {
+        let Some(color) = self.colors.as_ref().and_then(|c| color(c).get_termcolor()) else {
+            return text.to_owned();
+        };
+        let mut buffer = Buffer::ansi();
+        let painted = buffer
+            .set_color(ColorSpec::new().set_fg(Some(color)))
+            .and_then(|_| buffer.write_all(text.as_bytes()))
+            .and_then(|_| buffer.reset());
+        match painted {
+            Ok(()) => String::from_utf8_lossy(buffer.as_slice()).into_owned(),
+            Err(_) => text.to_owned(),
+        }
+    }
+
+    /// Paint `text` in `color`, picking out the entry's tags in the tag color.
+    fn paint_text(&self, text: &str, color: impl Fn(&ColorConfig) -> TextColor) -> String {
+        lazy_static! {
+            static ref WORD_RE: Regex = Regex::new(r"\S+|\s+").unwrap();
+        }
+        if self.colors.is_none() {
+            return text.to_owned();
+        }
+        WORD_RE
+            .find_iter(text)
+            .map(
+                |word| match self.tags.iter().any(|tag| tag == word.as_str()) {
+                    true => self.paint(word.as_str(), ColorConfig::tags),
+                    false => self.paint(word.as_str(), &color),
+                },
+            )
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::journal::Journal;
+
+    const JOURNAL: &str = "[2022-12-31 11:00:00 PM] Last one of the year.
+Party with @alice and @bob tonight.
+
+[2023-01-12 08:51:57 AM] A long day at work with a title that goes on and on and on. *
+Meetings all day @work
+
+Then home.
+
+[2023-01-12 09:30:00 PM] Evening.
+";
+
+    fn journal() -> Journal {
+        Journal::parse("default", JOURNAL, "%F %r").unwrap()
+    }
+
+    fn options() -> FormatOptions {
+        FormatOptions {
+            linewrap: Some(40),
+            highlight: true,
+            ..FormatOptions::for_test("%F %R")
+        }
+    }
+
+    pub fn render(display: DisplayConfig) -> String {
+        let journal = journal();
+        let entries = journal.entries().iter().collect::<Vec<_>>();
+        formatter(display, options()).format_entries(&entries)
+    }
+
+    #[test]
+    fn test_wrap() {
+        assert_eq!(
+            wrap("the quick brown fox jumps", 10, "", ""),
+            ["the quick", "brown fox", "jumps"]
+        );
+        assert_eq!(
+            wrap("one two three", 8, "| ", "| "),
+            ["| one", "| two", "| three"]
+        );
+        assert_eq!(wrap("abcdefghij", 4, "", ""), ["abcd", "efgh", "ij"]);
+        assert!(wrap("   ", 4, "", "").is_empty());
+    }
+
+    #[test]
+    fn test_tag_counts() {
+        let journal = journal();
+        let entries = journal.entries().iter().collect::<Vec<_>>();
+        let counts = tag_counts(&entries, "#@");
+        assert_eq!(counts.len(), 3);
+        assert!(counts.values().all(|&count| count == 1));
+    }
+
+    #[test]
+    fn test_text() {
+        assert_eq!(
+            render(DisplayConfig::Text),
+            "[2022-12-31 23:00] Last one of the year.
+Party with @alice and @bob tonight.
+
+[2023-01-12 08:51] A long day at work with a title that goes on and on and on. *
+Meetings all day @work
+
+Then home.
+
+[2023-01-12 21:30] Evening.
+"
+        );
+    }
+
+    #[test]
+    fn test_short() {
+        assert_eq!(
+            render(DisplayConfig::Short),
+            "2022-12-31 23:00 Last one of the year.
+2023-01-12 08:51 A long day at work with a title that goes on and on and on. *
+2023-01-12 21:30 Evening.
+"
+        );
+    }
+
+    #[test]
+    fn test_pretty() {
+        assert_eq!(
+            render(DisplayConfig::Pretty),
+            "2022-12-31 23:00 Last one of the year.
+| Party with @alice and @bob tonight.
+
+2023-01-12 08:51 A long day at work with
+a title that goes on and on and on. *
+| Meetings all day @work
+|
+| Then home.
+
+2023-01-12 21:30 Evening.
+"
+        );
+    }
+
+    #[test]
+    fn test_dates() {
+        assert_eq!(
+            render(DisplayConfig::Dates),
+            "2022-12-31, 1\n2023-01-12, 2\n"
+        );
+    }
+
+    #[test]
+    fn test_tags() {
+        assert_eq!(
+            render(DisplayConfig::Tags),
+            "@alice               : 1\n@bob                 : 1\n@work                : 1\n"
+        );
+    }
+
+    #[test]
+    fn test_json() {
+        let value: serde_json::Value = serde_json::from_str(&render(DisplayConfig::Json)).unwrap();
+        assert_eq!(value["tags"]["@alice"], 1);
+        assert_eq!(value["entries"].as_array().unwrap().len(), 3);
+        assert_eq!(
+            value["entries"][1],
+            serde_json::json!({
+                "title": "A long day at work with a title that goes on and on and on. *",
+                "body": "Meetings all day @work\n\nThen home.",
+                "date": "2023-01-12",
+                "time": "08:51:57",
+                "tags": ["@work"],
+                "starred": true,
+            })
+        );
+    }
+
+    #[test]
+    fn test_markdown() {
+        assert_eq!(
+            render(DisplayConfig::Markdown),
+            "# 2022
+
+## December
+
+### 2022-12-31 23:00 Last one of the year.
+
+Party with @alice and @bob tonight.
+
+# 2023
+
+## January
+
+### 2023-01-12 08:51 A long day at work with a title that goes on and on and on. *
+
+Meetings all day @work
+
+Then home.
+
+### 2023-01-12 21:30 Evening.
+
+"
+        );
+    }
+
+    #[test]
+    fn test_xml() {
+        let xml = render(DisplayConfig::Xml);
+        assert!(xml.starts_with("<?xml version=\"1.0\" ?>\n<journal>\n\t<tags>\n"));
+        assert!(xml.contains("\t\t<tag name=\"@alice\">1</tag>\n"));
+        assert!(xml.contains(
+            "\t\t<entry date=\"2023-01-12T08:51:57\" starred=\"\">\n\t\t\t<tag name=\"@work\"/>\n"
+        ));
+        assert!(xml.ends_with("\t</entries>\n</journal>\n"));
+    }
+
+    #[test]
+    fn test_yaml() {
+        let yaml = render(DisplayConfig::Yaml);
+        assert!(yaml.starts_with(
+            "---
+title: \"Last one of the year.\"
+date: 2022-12-31 23:00
+starred: false
+tags: alice, bob
+body: |
+    Party with @alice and @bob tonight.
+---
+"
+        ));
+    }
+
+    #[test]
+    fn test_boxed() {
+        assert_eq!(
+            render(DisplayConfig::Boxed)
+                .lines()
+                .take(4)
+                .collect::<Vec<_>>(),
+            [
+                "┎──────────────────────╮2022-12-31 23:00",
+                "┃ Last one of the      ╘═══════════════╕",
+                "┃ year.                               │",
+                "┠╌╌╌╌╌╌╌╌╌╌╌╌╌╌╌╌╌╌╌╌╌╌╌╌╌╌╌╌╌╌╌╌╌╌╌╌╌┤",
+            ]
+        );
+    }
+}
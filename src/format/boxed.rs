@@ -0,0 +1,88 @@
+use super::{ljust, wrap, FormatOptions, Formatter};
+use crate::entry::Entry;
+
+/// Each entry drawn as a card, with the date on a tab at the top right.
+pub struct Boxed(pub FormatOptions);
+
+const TOP_LEFT: char = '┎';
+const TOP: char = '─';
+const TAB_LEFT: char = '╮';
+const TAB_BOTTOM_LEFT: char = '╘';
+const TAB_BOTTOM: char = '═';
+const TAB_RIGHT: char = '╕';
+const LEFT: &str = "┃";
+const RIGHT: char = '│';
+const RULE_LEFT: char = '┠';
+const RULE: char = '╌';
+const RULE_RIGHT: char = '┤';
+const BOTTOM_LEFT: char = '┖';
+const BOTTOM_RIGHT: char = '┘';
+
+fn repeat(c: char, n: usize) -> String {
+    std::iter::repeat_n(c, n).collect()
+}
+
+impl Boxed {
+    fn format_entry(&self, entry: &Entry) -> String {
+        let date = entry.time.format(&self.0.timeformat).to_string();
+        let date_len = date.chars().count();
+        let linewrap = self.0.linewrap.unwrap_or(78).max(date_len + 4);
+        let title_width = linewrap.saturating_sub(date_len + 2).max(1);
+        let body_width = linewrap - 2;
+        let indent = format!("{LEFT} ");
+
+        let mut card = vec![format!(
+            "{TOP_LEFT}{}{TAB_LEFT}{date}",
+            repeat(TOP, title_width)
+        )];
+        let title_lines = wrap(entry.title(), title_width, &indent, &indent);
+        let first = title_lines.first().cloned().unwrap_or_default();
+        card.push(format!(
+            "{}{TAB_BOTTOM_LEFT}{}{TAB_RIGHT}",
+            ljust(&first, title_width + 1),
+            repeat(TAB_BOTTOM, date_len - 1)
+        ));
+        if title_lines.len() > 1 {
+            let rest = title_lines[1..]
+                .iter()
+                .map(|line| line.strip_prefix(&indent).unwrap_or(line))
+                .collect::<Vec<_>>()
+                .join(" ");
+            for line in wrap(&rest, body_width, &indent, &indent) {
+                card.push(format!("{}{RIGHT}", ljust(&line, body_width)));
+            }
+        }
+        if !entry.text().is_empty() {
+            card.push(format!(
+                "{RULE_LEFT}{}{RULE_RIGHT}",
+                repeat(RULE, body_width - 1)
+            ));
+            for line in entry.text().lines() {
+                let lines = wrap(line, body_width, &indent, &indent);
+                let lines = match lines.is_empty() {
+                    true => vec![LEFT.to_owned()],
+                    false => lines,
+                };
+                for line in lines {
+                    card.push(format!("{}{RIGHT}", ljust(&line, body_width)));
+                }
+            }
+        }
+        card.push(format!(
+            "{BOTTOM_LEFT}{}{BOTTOM_RIGHT}",
+            repeat(TOP, body_width - 1)
+        ));
+        card.join("\n")
+    }
+}
+
+impl Formatter for Boxed {
+    fn format_entries(&self, entries: &[&Entry]) -> String {
+        entries
+            .iter()
+            .map(|entry| self.format_entry(entry))
+            .collect::<Vec<_>>()
+            .join("\n")
+            + "\n"
+    }
+}
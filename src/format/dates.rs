@@ -0,0 +1,20 @@
+use indexmap::IndexMap;
+
+use super::Formatter;
+use crate::entry::Entry;
+
+/// Number of entries per day.
+pub struct Dates;
+
+impl Formatter for Dates {
+    fn format_entries(&self, entries: &[&Entry]) -> String {
+        let mut counts = IndexMap::<_, usize>::new();
+        for entry in entries {
+            *counts.entry(entry.time.date()).or_default() += 1;
+        }
+        counts
+            .iter()
+            .map(|(date, count)| format!("{}, {count}\n", date.format("%F")))
+            .collect()
+    }
+}
@@ -0,0 +1,46 @@
+use indexmap::IndexMap;
+use serde::Serialize;
+
+use super::{tag_counts, FormatOptions, Formatter};
+use crate::entry::Entry;
+
+/// jrnl's JSON export: tag counts and an array of entries.
+pub struct Json(pub FormatOptions);
+
+#[derive(Serialize)]
+struct JsonJournal<'a> {
+    tags: IndexMap<String, usize>,
+    entries: Vec<JsonEntry<'a>>,
+}
+
+#[derive(Serialize)]
+struct JsonEntry<'a> {
+    title: &'a str,
+    body: &'a str,
+    date: String,
+    time: String,
+    tags: Vec<String>,
+    starred: bool,
+}
+
+impl Formatter for Json {
+    fn format_entries(&self, entries: &[&Entry]) -> String {
+        let journal = JsonJournal {
+            tags: tag_counts(entries, &self.0.tagsymbols),
+            entries: entries
+                .iter()
+                .map(|entry| JsonEntry {
+                    title: entry.title(),
+                    body: entry.text(),
+                    date: entry.time.format("%F").to_string(),
+                    time: entry.time.format("%T").to_string(),
+                    tags: entry.tags(&self.0.tagsymbols),
+                    starred: entry.starred(),
+                })
+                .collect(),
+        };
+        let mut out = serde_json::to_string_pretty(&journal).expect("journal is serializable");
+        out.push('\n');
+        out
+    }
+}
@@ -0,0 +1,74 @@
+use chrono::Datelike;
+use lazy_static::lazy_static;
+use regex::Regex;
+
+use super::{FormatOptions, Formatter};
+use crate::entry::Entry;
+
+/// Markdown grouped under year and month headings. Headings in entry bodies
+/// are demoted so they nest under the entry's own heading.
+pub struct Markdown(pub FormatOptions);
+
+impl Markdown {
+    /// A single entry under a heading of `level`.
+    fn entry_with_heading(&self, entry: &Entry, level: usize) -> String {
+        lazy_static! {
+            static ref ATX_RE: Regex = Regex::new(r"^#+ ").unwrap();
+            static ref SETEXT_H1_RE: Regex = Regex::new(r"^=+$").unwrap();
+            static ref SETEXT_H2_RE: Regex = Regex::new(r"^-+$").unwrap();
+        }
+        let heading = "#".repeat(level);
+        let date = entry.time.format(&self.0.timeformat);
+        let mut out = format!("{heading} {date} {}\n", entry.title());
+        if entry.text().is_empty() {
+            out.push('\n');
+            return out;
+        }
+
+        let mut body: Vec<String> = vec![String::new()];
+        for line in entry.text().lines() {
+            let previous = body.last().map(|l| !l.trim().is_empty()).unwrap_or(false);
+            if ATX_RE.is_match(line) {
+                body.push(format!("{heading}{line}"));
+            } else if previous && SETEXT_H1_RE.is_match(line.trim_end()) {
+                let title = body.pop().unwrap_or_default();
+                body.push(format!("{heading}# {title}"));
+            } else if previous && SETEXT_H2_RE.is_match(line.trim_end()) {
+                let title = body.pop().unwrap_or_default();
+                body.push(format!("{heading}## {title}"));
+            } else {
+                body.push(line.to_owned());
+            }
+        }
+        out.push_str(&body.join("\n"));
+        out.push('\n');
+        out
+    }
+}
+
+impl Formatter for Markdown {
+    fn format_entries(&self, entries: &[&Entry]) -> String {
+        let mut out = vec![];
+        let mut year_month = None;
+        for entry in entries {
+            let (year, month) = (entry.time.year(), entry.time.month());
+            if year_month.map(|(y, _)| y) != Some(year) {
+                out.push(format!("# {year}\n"));
+            }
+            if year_month != Some((year, month)) {
+                out.push(format!("## {}\n", entry.time.format("%B")));
+            }
+            year_month = Some((year, month));
+            out.push(self.entry_with_heading(entry, 3));
+        }
+        out.join("\n")
+    }
+
+    fn extension(&self) -> Option<&'static str> {
+        Some("md")
+    }
+
+    fn format_entry(&self, entry: &Entry) -> String {
+        self.entry_with_heading(entry, 1)
+    }
+}
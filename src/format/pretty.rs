@@ -0,0 +1,63 @@
+use super::{wrap, FormatOptions, Formatter, Painter};
+use crate::entry::Entry;
+use crate::settings::ColorConfig;
+
+/// Date and title followed by the body, indented and wrapped to `linewrap`.
+pub struct Pretty(pub FormatOptions);
+
+impl Pretty {
+    fn format_entry(&self, entry: &Entry) -> String {
+        let options = &self.0;
+        let painter = Painter::new(options, entry);
+        let date = entry.time.format(&options.timeformat).to_string();
+        let indent = match options.indent_character {
+            c if c.is_whitespace() => String::new(),
+            c => format!("{c} "),
+        };
+
+        let heading = format!("{date} {}", entry.title());
+        let heading = match options.linewrap {
+            Some(columns) => wrap(&heading, columns, "", "").join("\n"),
+            None => heading,
+        };
+        let heading = match heading.strip_prefix(&date) {
+            Some(title) => format!(
+                "{}{}",
+                painter.paint(&date, ColorConfig::date),
+                painter.paint_text(title, ColorConfig::title)
+            ),
+            None => painter.paint_text(&heading, ColorConfig::title),
+        };
+
+        let mut out = heading;
+        out.push('\n');
+        for line in entry.text().lines() {
+            let wrapped = match options.linewrap {
+                Some(columns) => wrap(line, columns, &indent, &indent),
+                None if line.trim().is_empty() => vec![],
+                None => vec![format!("{indent}{line}")],
+            };
+            if wrapped.is_empty() {
+                out.push_str(indent.trim_end());
+                out.push('\n');
+            }
+            for line in wrapped {
+                let body = line.strip_prefix(&indent).unwrap_or(&line);
+                out.push_str(&indent);
+                out.push_str(&painter.paint_text(body, ColorConfig::body));
+                out.push('\n');
+            }
+        }
+        out
+    }
+}
+
+impl Formatter for Pretty {
+    fn format_entries(&self, entries: &[&Entry]) -> String {
+        entries
+            .iter()
+            .map(|entry| self.format_entry(entry))
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+}
@@ -0,0 +1,23 @@
+use super::{FormatOptions, Formatter, Painter};
+use crate::entry::Entry;
+use crate::settings::ColorConfig;
+
+/// One line per entry: date and title.
+pub struct Short(pub FormatOptions);
+
+impl Formatter for Short {
+    fn format_entries(&self, entries: &[&Entry]) -> String {
+        entries
+            .iter()
+            .map(|entry| {
+                let painter = Painter::new(&self.0, entry);
+                let date = entry.time.format(&self.0.timeformat).to_string();
+                format!(
+                    "{} {}\n",
+                    painter.paint(&date, ColorConfig::date),
+                    painter.paint_text(entry.title(), ColorConfig::title)
+                )
+            })
+            .collect()
+    }
+}
@@ -0,0 +1,18 @@
+use super::{tag_counts, FormatOptions, Formatter};
+use crate::entry::Entry;
+
+/// Every tag in the selection with the number of entries it appears in.
+pub struct Tags(pub FormatOptions);
+
+impl Formatter for Tags {
+    fn format_entries(&self, entries: &[&Entry]) -> String {
+        let counts = tag_counts(entries, &self.0.tagsymbols);
+        if counts.is_empty() {
+            return "[No tags found in journal.]\n".to_owned();
+        }
+        counts
+            .iter()
+            .map(|(tag, count)| format!("{tag:20} : {count}\n"))
+            .collect()
+    }
+}
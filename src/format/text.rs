@@ -0,0 +1,19 @@
+use super::{FormatOptions, Formatter};
+use crate::entry::Entry;
+
+/// jrnl's plain text journal format.
+pub struct Text(pub FormatOptions);
+
+impl Formatter for Text {
+    fn format_entries(&self, entries: &[&Entry]) -> String {
+        entries
+            .iter()
+            .map(|entry| entry.format(&self.0.timeformat))
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    fn extension(&self) -> Option<&'static str> {
+        Some("txt")
+    }
+}
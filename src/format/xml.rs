@@ -0,0 +1,43 @@
+use super::{tag_counts, FormatOptions, Formatter};
+use crate::entry::Entry;
+
+/// jrnl's XML export, laid out like Python's `toprettyxml`.
+pub struct Xml(pub FormatOptions);
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+impl Formatter for Xml {
+    fn format_entries(&self, entries: &[&Entry]) -> String {
+        let mut out = String::from("<?xml version=\"1.0\" ?>\n<journal>\n\t<tags>\n");
+        for (tag, count) in tag_counts(entries, &self.0.tagsymbols) {
+            out.push_str(&format!(
+                "\t\t<tag name=\"{}\">{count}</tag>\n",
+                escape(&tag)
+            ));
+        }
+        out.push_str("\t</tags>\n\t<entries>\n");
+        for entry in entries {
+            let starred = match entry.starred() {
+                true => " starred=\"\"",
+                false => "",
+            };
+            let date = entry.time.format("%FT%T");
+            out.push_str(&format!("\t\t<entry date=\"{date}\"{starred}>\n"));
+            for tag in entry.tags(&self.0.tagsymbols) {
+                out.push_str(&format!("\t\t\t<tag name=\"{}\"/>\n", escape(&tag)));
+            }
+            let text = match entry.text() {
+                "" => entry.title().to_owned(),
+                text => format!("{}\n{text}", entry.title()),
+            };
+            out.push_str(&format!("\t\t\t{}\n\t\t</entry>\n", escape(&text)));
+        }
+        out.push_str("\t</entries>\n</journal>\n");
+        out
+    }
+}
@@ -0,0 +1,43 @@
+use super::{FormatOptions, Formatter};
+use crate::entry::Entry;
+
+/// jrnl's YAML export: one front-matter style document per entry.
+pub struct Yaml(pub FormatOptions);
+
+impl Formatter for Yaml {
+    fn format_entries(&self, entries: &[&Entry]) -> String {
+        entries
+            .iter()
+            .map(|entry| self.format_entry(entry))
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    fn extension(&self) -> Option<&'static str> {
+        Some("yaml")
+    }
+
+    fn format_entry(&self, entry: &Entry) -> String {
+        let title = entry.title().replace('\\', "\\\\").replace('"', "\\\"");
+        let tags = entry
+            .tags(&self.0.tagsymbols)
+            .iter()
+            .map(|tag| tag.chars().skip(1).collect::<String>())
+            .collect::<Vec<_>>()
+            .join(", ");
+        let mut out = format!(
+            "---\ntitle: \"{title}\"\ndate: {}\nstarred: {}\ntags: {tags}\nbody: |\n",
+            entry.time.format(&self.0.timeformat),
+            entry.starred()
+        );
+        for line in entry.text().lines() {
+            if !line.trim().is_empty() {
+                out.push_str("    ");
+                out.push_str(line);
+            }
+            out.push('\n');
+        }
+        out.push_str("---\n");
+        out
+    }
+}
@@ -1,5 +1,157 @@
-use crate::cli::ImportArgs;
+use chrono::{Local, NaiveDateTime};
+use std::collections::HashSet;
+use std::fs;
+use std::io::{self, Read};
+use std::path::Path;
 
-pub fn import(_args: &ImportArgs) {
-    todo!()
+use crate::cli::{ImportArgs, ImportFormat};
+use crate::compose;
+use crate::entry::Entry;
+use crate::error::{self, JrnlError, JrnlErrorKind};
+use crate::journal::{self, Journal};
+use crate::settings::Settings;
+use crate::store;
+
+mod dayone;
+mod json;
+mod markdown;
+mod yaml;
+
+type Result<T> = std::result::Result<T, Box<dyn std::error::Error>>;
+
+/// Files that came with the imported entries, by file name.
+type Attachments = Vec<(String, Vec<u8>)>;
+
+/// Import entries from another journal, skipping those already present. The
+/// journal is left untouched unless the whole source parses.
+pub fn import(
+    args: &ImportArgs,
+    settings: &Settings,
+    journal_name: &str,
+    journal_file: &Path,
+) -> Result<()> {
+    let (imported, attachments) = read(args, settings, journal_name).map_err(|err| {
+        JrnlError::new(error::kind_of(err.as_ref()).unwrap_or(JrnlErrorKind::InvalidImport))
+            .context(format!("failed to read '{}', nothing imported", args.file))
+            .source(err.to_string())
+    })?;
+
+    // only the days being imported into can have duplicates
+    let days = imported
+        .iter()
+        .map(|entry| entry.time.date())
+        .collect::<HashSet<_>>();
+    let mut store = store::open(settings, journal_name, journal_file)?;
+    let mut journal = store.load(&|date| days.contains(&date))?;
+    let (added, skipped) = journal.merge(imported);
+    if added > 0 {
+        store.replace(&journal)?;
+    }
+    eprintln!("[{added} imported to {journal_name} journal, {skipped} skipped as duplicates]");
+    save_attachments(journal_file, &attachments)
+}
+
+/// Save attachments that aren't there yet in the attachments folder next to
+/// the journal file, or inside a folder journal.
+fn save_attachments(journal_file: &Path, attachments: &[(String, Vec<u8>)]) -> Result<()> {
+    if attachments.is_empty() {
+        return Ok(());
+    }
+    let journal_file = store::sqlite_path(journal_file).unwrap_or(journal_file);
+    let dir = match journal_file.is_dir() {
+        true => journal_file,
+        false => journal_file.parent().unwrap_or(Path::new(".")),
+    }
+    .join(dayone::ATTACHMENTS_DIR);
+    let mut saved = 0;
+    for (name, contents) in attachments {
+        let path = dir.join(name);
+        if !fs::exists(&path)? {
+            fs::create_dir_all(&dir)?;
+            journal::write_atomic(&path, contents)?;
+            saved += 1;
+        }
+    }
+    eprintln!("[{saved} attachments saved to {}]", dir.display());
+    Ok(())
+}
+
+/// Read and parse the whole import source.
+fn read(
+    args: &ImportArgs,
+    settings: &Settings,
+    journal_name: &str,
+) -> Result<(Vec<Entry>, Attachments)> {
+    let timeformat = settings.timeformat(journal_name)?;
+    let tagsymbol = settings
+        .tagsymbols(journal_name)?
+        .chars()
+        .next()
+        .unwrap_or('@');
+    let default_time = compose::default_time(settings, journal_name)?;
+
+    let path = Path::new(&args.file);
+    if args.file != "stdin" && path.is_dir() {
+        return match args.format {
+            ImportFormat::Markdown => {
+                Ok((markdown::parse_dir(path, default_time, tagsymbol)?, vec![]))
+            }
+            _ => Err("only markdown notes can be imported from a directory".into()),
+        };
+    }
+    let source = match args.file.as_str() {
+        "stdin" => {
+            let mut source = vec![];
+            io::stdin().read_to_end(&mut source)?;
+            source
+        }
+        path if matches!(args.format, ImportFormat::DayOne) => fs::read(path)?,
+        // may be an old encrypted journal
+        path => journal::unlock(Path::new(path), path, None, true)?
+            .0
+            .into_bytes(),
+    };
+
+    if let ImportFormat::DayOne = args.format {
+        let export = dayone::parse(&source, &Local, tagsymbol)?;
+        return Ok((export.entries, export.attachments));
+    }
+    let source = std::str::from_utf8(&source)?;
+    let entries = match args.format {
+        ImportFormat::Jrnl => Journal::parse("import", source, &timeformat)?.into_entries(),
+        ImportFormat::Json => json::parse(source)?,
+        ImportFormat::Yaml => yaml::parse(source, &timeformat)?,
+        ImportFormat::Markdown => markdown::parse(source, default_time, tagsymbol)?,
+        ImportFormat::DayOne => unreachable!("handled above"),
+    };
+    Ok((entries, vec![]))
+}
+
+/// `text` with the `tags` it doesn't mention yet appended on a line of their
+/// own, marked with `tagsymbol` and with spaces replaced by underscores.
+fn with_tags(text: &str, tags: &[String], tagsymbol: char) -> String {
+    let missing = tags
+        .iter()
+        .map(|tag| {
+            format!(
+                "{tagsymbol}{}",
+                tag.split_whitespace().collect::<Vec<_>>().join("_")
+            )
+        })
+        .filter(|tag| !text.split_whitespace().any(|word| word == tag))
+        .collect::<Vec<_>>();
+    match (text.trim_end(), missing.is_empty()) {
+        (text, true) => text.to_owned(),
+        ("", false) => missing.join(" "),
+        (text, false) => format!("{text}\n\n{}", missing.join(" ")),
+    }
+}
+
+/// An entry from an export format that stores whether it's starred separately
+/// rather than as a `*` in the title.
+fn entry(time: NaiveDateTime, title: &str, body: &str, starred: bool) -> Entry {
+    match starred && !title.contains('*') {
+        true => Entry::new(time, &format!("*{title}"), body),
+        false => Entry::new(time, title, body),
+    }
 }
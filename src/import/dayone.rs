@@ -0,0 +1,217 @@
+use chrono::{DateTime, TimeZone};
+use lazy_static::lazy_static;
+use regex::{Captures, Regex};
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::io::{Cursor, Read};
+use std::path::Path;
+
+use super::{entry, with_tags, Result};
+use crate::entry::Entry;
+
+/// Where photos go, next to the journal file.
+pub const ATTACHMENTS_DIR: &str = "attachments";
+
+#[derive(Deserialize)]
+struct DayOneJournal {
+    entries: Vec<DayOneEntry>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct DayOneEntry {
+    creation_date: String,
+    #[serde(default)]
+    text: String,
+    #[serde(default)]
+    starred: bool,
+    #[serde(default)]
+    tags: Vec<String>,
+    #[serde(default)]
+    photos: Vec<DayOnePhoto>,
+}
+
+#[derive(Deserialize)]
+struct DayOnePhoto {
+    identifier: String,
+    md5: String,
+    #[serde(rename = "type", default = "default_photo_type")]
+    kind: String,
+}
+
+fn default_photo_type() -> String {
+    "jpeg".to_owned()
+}
+
+impl DayOnePhoto {
+    fn file_name(&self) -> String {
+        format!("{}.{}", self.md5, self.kind)
+    }
+}
+
+/// The entries of a Day One export, and the photos found in its archive
+/// keyed by the file name they're linked as.
+pub struct DayOne {
+    pub entries: Vec<Entry>,
+    pub attachments: Vec<(String, Vec<u8>)>,
+}
+
+/// Read a Day One JSON export, or a zip archive of one with its photos. Times
+/// are converted to `tz`, and tags not already in the text are appended to it
+/// with `tagsymbol`.
+pub fn parse<Tz: TimeZone>(data: &[u8], tz: &Tz, tagsymbol: char) -> Result<DayOne> {
+    if !data.starts_with(b"PK\x03\x04") {
+        let journal = serde_json::from_slice(data)?;
+        return Ok(DayOne {
+            entries: entries(journal, tz, tagsymbol)?,
+            attachments: vec![],
+        });
+    }
+
+    let mut archive = zip::ZipArchive::new(Cursor::new(data))?;
+    let mut export = DayOne {
+        entries: vec![],
+        attachments: vec![],
+    };
+    for i in 0..archive.len() {
+        let mut file = archive.by_index(i)?;
+        let Some(path) = file.enclosed_name() else {
+            continue;
+        };
+        let mut contents = vec![];
+        if path.starts_with("photos") {
+            let Some(name) = path.file_name() else {
+                continue;
+            };
+            file.read_to_end(&mut contents)?;
+            let name = name.to_string_lossy().into_owned();
+            export.attachments.push((name, contents));
+        } else if path.extension() == Some("json".as_ref()) {
+            file.read_to_end(&mut contents)?;
+            let journal = serde_json::from_slice(&contents)
+                .map_err(|err| format!("{}: {err}", Path::new(&path).display()))?;
+            export.entries.extend(entries(journal, tz, tagsymbol)?);
+        }
+    }
+    Ok(export)
+}
+
+fn entries<Tz: TimeZone>(journal: DayOneJournal, tz: &Tz, tagsymbol: char) -> Result<Vec<Entry>> {
+    lazy_static! {
+        static ref MOMENT_RE: Regex =
+            Regex::new(r"!\[[^\]]*\]\(dayone-moment://([[:alnum:]]+)\)").unwrap();
+    }
+    journal
+        .entries
+        .into_iter()
+        .map(|e| {
+            let time = DateTime::parse_from_rfc3339(&e.creation_date)
+                .map_err(|err| format!("invalid creationDate '{}': {err}", e.creation_date))?
+                .with_timezone(tz)
+                .naive_local();
+
+            let photos = e
+                .photos
+                .iter()
+                .map(|photo| (photo.identifier.as_str(), photo.file_name()))
+                .collect::<HashMap<_, _>>();
+            let text =
+                MOMENT_RE.replace_all(&e.text, |caps: &Captures| match photos.get(&caps[1]) {
+                    Some(file_name) => format!("![]({ATTACHMENTS_DIR}/{file_name})"),
+                    None => caps[0].to_owned(),
+                });
+            // Day One titles are usually markdown headings
+            let text = text.trim_start().trim_start_matches("# ");
+            let text = with_tags(text, &e.tags, tagsymbol);
+
+            let composed = Entry::compose(time, &text);
+            Ok(entry(time, composed.title(), composed.text(), e.starred))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{FixedOffset, NaiveDate};
+    use std::io::Write;
+    use zip::write::SimpleFileOptions;
+
+    const EXPORT: &str = r##"{
+  "metadata": {"version": "1.0"},
+  "entries": [
+    {
+      "uuid": "9A1B",
+      "creationDate": "2020-05-01T22:30:00Z",
+      "timeZone": "America/New_York",
+      "text": "# Road trip\n\nMade it to the coast. #travel\n\n![](dayone-moment://F00D)",
+      "starred": true,
+      "tags": ["travel", "family time"],
+      "photos": [{"identifier": "F00D", "md5": "0123abcd", "type": "jpeg"}]
+    },
+    {
+      "creationDate": "2020-05-02T08:00:00Z",
+      "text": "Quiet morning."
+    }
+  ]
+}"##;
+
+    fn tz() -> FixedOffset {
+        FixedOffset::east_opt(2 * 3600).unwrap()
+    }
+
+    #[test]
+    fn test_parse_json() {
+        let export = parse(EXPORT.as_bytes(), &tz(), '#').unwrap();
+        assert!(export.attachments.is_empty());
+        let [trip, morning] = &export.entries[..] else {
+            panic!("expected two entries");
+        };
+
+        let time = NaiveDate::from_ymd_opt(2020, 5, 2)
+            .unwrap()
+            .and_hms_opt(0, 30, 0)
+            .unwrap();
+        assert_eq!(trip.time, time);
+        assert_eq!(trip.title(), "*Road trip");
+        assert!(trip.starred());
+        assert_eq!(
+            trip.text(),
+            "Made it to the coast. #travel\n\n![](attachments/0123abcd.jpeg)\n\n#family_time"
+        );
+        assert_eq!(trip.tags("#"), ["#travel", "#family_time"]);
+
+        assert_eq!(
+            morning.time.format("%F %T").to_string(),
+            "2020-05-02 10:00:00"
+        );
+        assert_eq!(morning.title(), "Quiet morning.");
+        assert!(!morning.starred());
+    }
+
+    #[test]
+    fn test_parse_zip() {
+        let mut zip = zip::ZipWriter::new(Cursor::new(vec![]));
+        let options = SimpleFileOptions::default();
+        zip.start_file("Journal.json", options).unwrap();
+        zip.write_all(EXPORT.as_bytes()).unwrap();
+        zip.start_file("photos/0123abcd.jpeg", options).unwrap();
+        zip.write_all(b"not really a jpeg").unwrap();
+        let data = zip.finish().unwrap().into_inner();
+
+        let export = parse(&data, &tz(), '@').unwrap();
+        assert_eq!(export.entries.len(), 2);
+        assert!(export.entries[0].text().ends_with("@travel @family_time"));
+        assert_eq!(
+            export.attachments,
+            [("0123abcd.jpeg".to_owned(), b"not really a jpeg".to_vec())]
+        );
+    }
+
+    #[test]
+    fn test_invalid() {
+        assert!(parse(b"{}", &tz(), '@').is_err());
+        let bad_date = r#"{"entries": [{"creationDate": "yesterday", "text": "x"}]}"#;
+        assert!(parse(bad_date.as_bytes(), &tz(), '@').is_err());
+    }
+}
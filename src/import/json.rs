@@ -0,0 +1,87 @@
+use chrono::{NaiveDate, NaiveTime};
+use serde::Deserialize;
+
+use super::{entry, Result};
+use crate::entry::Entry;
+
+/// The shape of jrnl's JSON export. Tags are left out since they're derived
+/// from the entry text anyway.
+#[derive(Deserialize)]
+struct JsonJournal {
+    entries: Vec<JsonEntry>,
+}
+
+#[derive(Deserialize)]
+struct JsonEntry {
+    title: String,
+    #[serde(default)]
+    body: String,
+    date: String,
+    time: String,
+    #[serde(default)]
+    starred: bool,
+}
+
+/// Entries from jrnl's JSON export.
+pub fn parse(source: &str) -> Result<Vec<Entry>> {
+    let journal: JsonJournal = serde_json::from_str(source)?;
+    journal
+        .entries
+        .into_iter()
+        .map(|e| {
+            let date = NaiveDate::parse_from_str(&e.date, "%F")
+                .map_err(|err| format!("invalid date '{}': {err}", e.date))?;
+            let time = NaiveTime::parse_from_str(&e.time, "%R")
+                .or_else(|_| NaiveTime::parse_from_str(&e.time, "%T"))
+                .map_err(|err| format!("invalid time '{}': {err}", e.time))?;
+            Ok(entry(date.and_time(time), &e.title, &e.body, e.starred))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::format::{formatter, FormatOptions};
+    use crate::journal::Journal;
+    use crate::settings::DisplayConfig;
+
+    const JOURNAL: &str = "[2013-06-09 03:45:12 PM] Life is good.
+But I'm better.
+
+[2013-06-10 03:40:59 PM] *Everything is alright @work
+Entries have \"quotes\", tags and
+
+blank lines.
+
+[2013-06-11 09:00:00 AM] No body.
+";
+
+    #[test]
+    fn test_round_trip() {
+        let journal = Journal::parse("default", JOURNAL, "%F %r").unwrap();
+        let options = FormatOptions::for_test("%F %r");
+        let entries = journal.entries().iter().collect::<Vec<_>>();
+        let exported = formatter(DisplayConfig::Json, options).format_entries(&entries);
+        assert_eq!(parse(&exported).unwrap(), journal.entries());
+    }
+
+    #[test]
+    fn test_starred_without_marker() {
+        let source = r#"{"entries": [{"title": "Big day", "body": "",
+            "date": "2023-01-11", "time": "09:00:30", "starred": true}]}"#;
+        let entries = parse(source).unwrap();
+        assert!(entries[0].starred());
+        assert_eq!(
+            entries[0].format("%F %T"),
+            "[2023-01-11 09:00:30] *Big day\n"
+        );
+    }
+
+    #[test]
+    fn test_invalid() {
+        assert!(parse("[]").is_err());
+        let source = r#"{"entries": [{"title": "x", "date": "tomorrow", "time": "09:00"}]}"#;
+        assert!(parse(source).is_err());
+    }
+}
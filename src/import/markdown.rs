@@ -0,0 +1,328 @@
+use chrono::{NaiveDate, NaiveDateTime, NaiveTime};
+use lazy_static::lazy_static;
+use regex::Regex;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use super::{with_tags, Result};
+use crate::entry::Entry;
+
+lazy_static! {
+    /// `## 2023-01-12 08:30 PM Title`, with the time and title optional.
+    static ref DATE_HEADING_RE: Regex = Regex::new(
+        r"^#{1,6}\s+(?P<date>\d{4}-\d{2}-\d{2})(?:[ T](?P<time>\d{1,2}:\d{2}(?::\d{2})?(?:\s*[AaPp][Mm])?))?(?:\s+(?P<title>.*?))?\s*$"
+    )
+    .unwrap();
+    /// `## 08:30 Title` in a daily note, which gives the date.
+    static ref TIME_HEADING_RE: Regex = Regex::new(
+        r"^#{1,6}\s+(?P<time>\d{1,2}:\d{2}(?::\d{2})?(?:\s*[AaPp][Mm])?)(?:\s+(?P<title>.*?))?\s*$"
+    )
+    .unwrap();
+    /// Year and month headings grouping entries, as in our own Markdown export.
+    static ref GROUP_HEADING_RE: Regex = Regex::new(
+        r"(?i)^#{1,6}\s+(\d{4}|january|february|march|april|may|june|july|august|september|october|november|december)\s*$"
+    )
+    .unwrap();
+    static ref DAILY_NOTE_RE: Regex = Regex::new(r"^(\d{4}-\d{2}-\d{2})").unwrap();
+}
+
+/// Entries from a single Markdown file, one per heading starting with a date.
+pub fn parse(source: &str, default_time: NaiveTime, tagsymbol: char) -> Result<Vec<Entry>> {
+    parse_note(source, None, default_time, tagsymbol)
+}
+
+/// Entries from a directory of daily notes named like `2023-01-12.md`,
+/// searched recursively. Other files are left alone.
+pub fn parse_dir(dir: &Path, default_time: NaiveTime, tagsymbol: char) -> Result<Vec<Entry>> {
+    let mut notes = vec![];
+    find_notes(dir, &mut notes)?;
+    if notes.is_empty() {
+        Err(format!("no YYYY-MM-DD.md notes found in {}", dir.display()))?
+    }
+    notes.sort();
+
+    let mut entries = vec![];
+    for (date, path) in notes {
+        let source = fs::read_to_string(&path)?;
+        let note = parse_note(&source, Some(date), default_time, tagsymbol)
+            .map_err(|err| format!("{}: {err}", path.display()))?;
+        entries.extend(note);
+    }
+    Ok(entries)
+}
+
+fn find_notes(dir: &Path, notes: &mut Vec<(NaiveDate, PathBuf)>) -> Result<()> {
+    for dir_entry in fs::read_dir(dir)? {
+        let path = dir_entry?.path();
+        if path.is_dir() {
+            find_notes(&path, notes)?;
+            continue;
+        }
+        if path.extension() != Some("md".as_ref()) {
+            continue;
+        }
+        let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+        let date = DAILY_NOTE_RE
+            .captures(&stem)
+            .and_then(|caps| NaiveDate::parse_from_str(&caps[1], "%F").ok());
+        if let Some(date) = date {
+            notes.push((date, path));
+        }
+    }
+    Ok(())
+}
+
+/// A heading that starts a new entry and the body lines below it.
+struct Section<'a> {
+    time: NaiveDateTime,
+    title: &'a str,
+    lines: Vec<&'a str>,
+}
+
+/// Entries from a note, dated `date` if it's a daily note. Text above the
+/// first dated heading is an entry of its own in a daily note and, apart from
+/// headings, an error otherwise.
+fn parse_note(
+    source: &str,
+    date: Option<NaiveDate>,
+    default_time: NaiveTime,
+    tagsymbol: char,
+) -> Result<Vec<Entry>> {
+    let (tags, source) = front_matter(source)?;
+    let mut preamble = vec![];
+    let mut sections: Vec<Section> = vec![];
+    let mut fenced = false;
+    for line in source.lines() {
+        if line.trim_start().starts_with("```") {
+            fenced = !fenced;
+        }
+        let heading = match fenced {
+            true => None,
+            false => heading(line, date, default_time)?,
+        };
+        match (heading, sections.last_mut()) {
+            (Some((time, title)), _) => sections.push(Section {
+                time,
+                title,
+                lines: vec![],
+            }),
+            (None, _) if !fenced && GROUP_HEADING_RE.is_match(line) => (),
+            (None, Some(section)) => section.lines.push(line),
+            (None, None) => preamble.push(line),
+        }
+    }
+
+    let mut entries = vec![];
+    let preamble = preamble.join("\n");
+    match date {
+        _ if preamble.trim().is_empty() => (),
+        Some(date) => {
+            // a daily note's own title heading
+            let text = preamble.trim_start().trim_start_matches('#').trim_start();
+            let time = date.and_time(default_time);
+            entries.push(Entry::compose(time, &with_tags(text, &tags, tagsymbol)));
+        }
+        // a document title is fine, anything else would be lost
+        None => {
+            let text = preamble
+                .lines()
+                .find(|l| !l.trim().is_empty() && !l.starts_with('#'));
+            if let Some(line) = text {
+                Err(format!("text before the first dated heading: '{line}'"))?
+            }
+        }
+    }
+    for section in sections {
+        let body = with_tags(section.lines.join("\n").trim(), &tags, tagsymbol);
+        entries.push(match section.title {
+            "" => Entry::compose(section.time, &body),
+            title => Entry::new(section.time, title, body.trim()),
+        });
+    }
+    Ok(entries)
+}
+
+/// The time and title of an entry heading, if `line` is one.
+fn heading(
+    line: &str,
+    date: Option<NaiveDate>,
+    default_time: NaiveTime,
+) -> Result<Option<(NaiveDateTime, &str)>> {
+    let (date, caps) = match (DATE_HEADING_RE.captures(line), date) {
+        (Some(caps), _) => {
+            let date = NaiveDate::parse_from_str(&caps["date"], "%F")
+                .map_err(|err| format!("invalid date in heading '{line}': {err}"))?;
+            (date, caps)
+        }
+        (None, Some(date)) => match TIME_HEADING_RE.captures(line) {
+            Some(caps) => (date, caps),
+            None => return Ok(None),
+        },
+        (None, None) => return Ok(None),
+    };
+    let time = match caps.name("time") {
+        Some(time) => {
+            parse_time(time.as_str()).ok_or_else(|| format!("invalid time in heading '{line}'"))?
+        }
+        None => default_time,
+    };
+    let title = caps.name("title").map(|t| t.as_str()).unwrap_or_default();
+    Ok(Some((date.and_time(time), title)))
+}
+
+fn parse_time(time: &str) -> Option<NaiveTime> {
+    let time = time
+        .to_uppercase()
+        .replace("AM", " AM")
+        .replace("PM", " PM");
+    let time = time.split_whitespace().collect::<Vec<_>>().join(" ");
+    ["%I:%M:%S %p", "%I:%M %p", "%H:%M:%S", "%H:%M"]
+        .iter()
+        .find_map(|format| NaiveTime::parse_from_str(&time, format).ok())
+}
+
+/// The `tags` from a note's YAML front matter, and the note without it. Tags
+/// may be a list or a string of comma or space separated words.
+fn front_matter(source: &str) -> Result<(Vec<String>, &str)> {
+    let Some(rest) = source.strip_prefix("---\n") else {
+        return Ok((vec![], source));
+    };
+    let Some(end) = rest
+        .find("\n---\n")
+        .or_else(|| rest.strip_suffix("\n---").map(|r| r.len()))
+    else {
+        return Ok((vec![], source));
+    };
+    let yaml: serde_yml::Value = serde_yml::from_str(&rest[..end])?;
+    let words = match yaml.get("tags") {
+        Some(serde_yml::Value::Sequence(tags)) => tags
+            .iter()
+            .filter_map(|tag| tag.as_str())
+            .map(str::to_owned)
+            .collect(),
+        Some(serde_yml::Value::String(tags)) => tags
+            .split([',', ' '])
+            .map(str::to_owned)
+            .collect::<Vec<_>>(),
+        _ => vec![],
+    };
+    let tags = words
+        .iter()
+        .map(|tag| tag.trim().trim_start_matches('#').to_owned())
+        .filter(|tag| !tag.is_empty())
+        .collect();
+    let body = rest.get(end + "\n---\n".len()..).unwrap_or_default();
+    Ok((tags, body))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::format::{formatter, FormatOptions};
+    use crate::journal::Journal;
+    use crate::settings::DisplayConfig;
+
+    fn nine() -> NaiveTime {
+        NaiveTime::from_hms_opt(9, 0, 0).unwrap()
+    }
+
+    fn formatted(entries: &[Entry]) -> String {
+        entries
+            .iter()
+            .map(|entry| entry.format("%F %R"))
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    #[test]
+    fn test_parse_file() {
+        let source = "---
+tags: [travel, '#family']
+---
+# Trip notes
+
+## 2023-01-12 5:30pm Arrived.
+Long drive.
+
+```
+## 2023-01-13 not a heading in a code block
+```
+
+## 2023-01-14
+Went home. #travel
+";
+        assert_eq!(
+            formatted(&parse(source, nine(), '#').unwrap()),
+            "[2023-01-12 17:30] Arrived.
+Long drive.
+
+```
+## 2023-01-13 not a heading in a code block
+```
+
+#travel #family
+
+[2023-01-14 09:00] Went home.
+#travel
+
+#family
+"
+        );
+    }
+
+    #[test]
+    fn test_text_before_first_heading() {
+        let err = parse("Some notes\n## 2023-01-12 Hi\n", nine(), '@').unwrap_err();
+        assert!(err.to_string().contains("Some notes"));
+        assert!(parse("## 2023-13-12 Hi\n", nine(), '@').is_err());
+    }
+
+    #[test]
+    fn test_parse_dir() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::create_dir(dir.path().join("2023")).unwrap();
+        fs::write(
+            dir.path().join("2023").join("2023-01-12.md"),
+            "---\ntags: work, meetings\n---\n# Busy day\nLots of meetings.\n\n## 14:15 Standup ran long\n",
+        )
+        .unwrap();
+        fs::write(dir.path().join("2023-01-11.md"), "Quiet. @work\n").unwrap();
+        fs::write(dir.path().join("ideas.md"), "not a daily note").unwrap();
+
+        assert_eq!(
+            formatted(&parse_dir(dir.path(), nine(), '@').unwrap()),
+            "[2023-01-11 09:00] Quiet.
+@work
+
+[2023-01-12 09:00] Busy day
+Lots of meetings.
+
+@work @meetings
+
+[2023-01-12 14:15] Standup ran long
+@work @meetings
+"
+        );
+        assert!(parse_dir(&dir.path().join("2023").join("nope"), nine(), '@').is_err());
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let journal = Journal::parse(
+            "default",
+            "[2013-06-09 03:45:00 PM] Life is good.
+But I'm better.
+
+[2013-07-10 03:40:00 PM] *Everything is alright @work
+
+[2014-01-11 09:00:00 AM] New year.
+",
+            "%F %r",
+        )
+        .unwrap();
+        let options = FormatOptions::for_test("%F %r");
+        let entries = journal.entries().iter().collect::<Vec<_>>();
+        let exported = formatter(DisplayConfig::Markdown, options).format_entries(&entries);
+        assert_eq!(parse(&exported, nine(), '@').unwrap(), journal.entries());
+    }
+}
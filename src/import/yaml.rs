@@ -0,0 +1,68 @@
+use chrono::NaiveDateTime;
+use serde::Deserialize;
+
+use super::{entry, Result};
+use crate::entry::Entry;
+
+/// One document of jrnl's YAML export. Tags are left out since they're
+/// derived from the entry text anyway.
+#[derive(Deserialize)]
+struct YamlEntry {
+    title: String,
+    date: String,
+    #[serde(default)]
+    starred: bool,
+    #[serde(default)]
+    body: Option<String>,
+}
+
+/// Entries from jrnl's YAML export, one document per entry with dates in
+/// `timeformat`.
+pub fn parse(source: &str, timeformat: &str) -> Result<Vec<Entry>> {
+    let mut entries = vec![];
+    for document in serde_yml::Deserializer::from_str(source) {
+        // the export closes every document with `---`, leaving empty ones
+        let Some(e) = Option::<YamlEntry>::deserialize(document)? else {
+            continue;
+        };
+        let time = NaiveDateTime::parse_from_str(&e.date, timeformat)
+            .map_err(|err| format!("invalid date '{}': {err}", e.date))?;
+        let body = e.body.unwrap_or_default();
+        entries.push(entry(time, &e.title, body.trim_end(), e.starred));
+    }
+    Ok(entries)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::format::{formatter, FormatOptions};
+    use crate::journal::Journal;
+    use crate::settings::DisplayConfig;
+
+    const JOURNAL: &str = "[2013-06-09 03:45:12 PM] Life is \"good\": yes.
+But I'm better.
+
+[2013-06-10 03:40:00 PM] *Everything is alright @work
+Entries have tags, #hashes and
+
+blank lines.
+
+[2013-06-11 09:00:00 AM] No body.
+";
+
+    #[test]
+    fn test_round_trip() {
+        let journal = Journal::parse("default", JOURNAL, "%F %r").unwrap();
+        let options = FormatOptions::for_test("%F %r");
+        let entries = journal.entries().iter().collect::<Vec<_>>();
+        let exported = formatter(DisplayConfig::Yaml, options).format_entries(&entries);
+        assert_eq!(parse(&exported, "%F %r").unwrap(), journal.entries());
+    }
+
+    #[test]
+    fn test_invalid() {
+        assert!(parse("---\ntitle: x\ndate: someday\n---\n", "%F %r").is_err());
+        assert!(parse("---\n- not\n- an entry\n---\n", "%F %r").is_err());
+    }
+}
@@ -1,25 +1,597 @@
-use crate::entry::{entries, Entry};
-use std::io::Read;
+use crate::credentials::CredentialStore;
+use crate::crypto;
+use crate::entry::{is_header, Entry};
+use crate::error::{JrnlError, JrnlErrorKind, ParseError};
+use chrono::NaiveDateTime;
+use std::fs::{self, OpenOptions};
+use std::io::{self, Read, Seek, SeekFrom, Write};
+use std::path::Path;
+use tempfile::NamedTempFile;
 
-#[derive(Debug)]
+const PASSWORD_ATTEMPTS: usize = 3;
+
+type Result<T> = std::result::Result<T, Box<dyn std::error::Error>>;
+
+#[derive(Debug, Clone)]
 pub struct Journal {
     entries: Vec<Entry>,
     #[allow(dead_code)]
     name: String,
+    /// Everything before the first entry exactly as `read` found it, blank
+    /// lines included, so that it survives rewriting the journal.
+    preamble: String,
+    /// The entries `read` parsed, in file order, so that those that haven't
+    /// changed are written back as they were.
+    sources: Vec<Source>,
+}
+
+/// An entry as `read` parsed it, with the text it was parsed from and the
+/// blank lines that followed it.
+#[derive(Debug, Clone)]
+struct Source {
+    entry: Entry,
+    text: String,
+    gap: String,
 }
 
 impl Journal {
-    #[allow(dead_code)]
     fn sort(&mut self) {
         self.entries.sort_by_key(|entry| entry.time)
     }
 
-    pub fn from_file<R: Read>(name: &str, reader: &mut R) -> Self {
-        let name = name.to_owned();
-        let mut raw = String::new();
-        reader.read_to_string(&mut raw).expect("read failed");
-        let entries = entries(raw.lines()).collect::<Vec<_>>();
+    pub fn from_entries(name: &str, entries: Vec<Entry>) -> Self {
+        Self {
+            entries,
+            name: name.to_owned(),
+            preamble: String::new(),
+            sources: vec![],
+        }
+    }
+
+    /// The journal with `entries` instead of its own, still written as they
+    /// were read where they haven't changed.
+    pub fn with_entries(mut self, entries: Vec<Entry>) -> Self {
+        self.entries = entries;
+        self
+    }
+
+    pub fn entries(&self) -> &[Entry] {
+        &self.entries
+    }
+
+    pub fn into_entries(self) -> Vec<Entry> {
+        self.entries
+    }
+
+    /// Parse a journal with entry times in `timeformat`, failing if there's
+    /// text before the first entry instead of dropping it.
+    pub fn parse(name: &str, text: &str, timeformat: &str) -> Result<Self> {
+        let journal = Self::read(name, text, timeformat);
+        match leading_problem(name, text, timeformat) {
+            Some(problem) if !journal.preamble.trim().is_empty() => Err(problem.into()),
+            _ => Ok(journal),
+        }
+    }
+
+    /// Parse a journal like `parse`, but keep any text before the first entry
+    /// as it is, which is then returned by `preamble`. Entries only start at
+    /// valid headers, so a line in a body that merely starts with `[` stays
+    /// part of it and the text before the first entry is all that can't be
+    /// parsed.
+    pub fn read(name: &str, text: &str, timeformat: &str) -> Self {
+        let starts = text
+            .split_inclusive('\n')
+            .scan(0, |offset, line| {
+                let start = *offset;
+                *offset += line.len();
+                Some((start, line.trim_end_matches(['\n', '\r'])))
+            })
+            .filter(|(_, line)| is_header(line, timeformat))
+            .map(|(start, _)| start)
+            .collect::<Vec<_>>();
+        let ends = starts.iter().skip(1).copied().chain([text.len()]);
+        let mut journal = Self::from_entries(name, vec![]);
+        journal.preamble = text[..starts.first().copied().unwrap_or(text.len())].to_owned();
+        for (&start, end) in starts.iter().zip(ends) {
+            let chunk = &text[start..end];
+            let blank = chunk
+                .split_inclusive('\n')
+                .rev()
+                .take_while(|line| line.trim().is_empty())
+                .map(str::len)
+                .sum::<usize>();
+            let (chunk, gap) = chunk.split_at(chunk.len() - blank);
+            match Entry::parse(&chunk.lines().collect::<Vec<_>>(), timeformat) {
+                Ok(entry) => {
+                    journal.entries.push(entry.clone());
+                    journal.sources.push(Source {
+                        entry,
+                        text: chunk.to_owned(),
+                        gap: gap.to_owned(),
+                    });
+                }
+                // never dropped, but written back with the text before it
+                Err(_) => match journal.sources.last_mut() {
+                    Some(previous) => {
+                        previous.text = [&previous.text, &previous.gap, chunk].concat();
+                        previous.gap = gap.to_owned();
+                    }
+                    None => journal.preamble.push_str(&text[start..end]),
+                },
+            }
+        }
+        journal
+    }
+
+    /// The text before the first entry, verbatim. Unless blank, it's text
+    /// `read` couldn't parse.
+    pub fn preamble(&self) -> &str {
+        &self.preamble
+    }
+
+    /// Add the `entries` not already in the journal, keeping it sorted by time.
+    /// Returns how many were added and how many skipped as duplicates.
+    pub fn merge(&mut self, entries: Vec<Entry>) -> (usize, usize) {
+        let (mut added, mut skipped) = (0, 0);
+        for entry in entries {
+            if self.entries.contains(&entry) {
+                skipped += 1;
+            } else {
+                self.entries.push(entry);
+                added += 1;
+            }
+        }
+        self.sort();
+        (added, skipped)
+    }
+
+    /// Add all of `entries`, even those already in the journal, keeping it
+    /// sorted by time.
+    pub fn insert(&mut self, entries: Vec<Entry>) {
+        self.entries.extend(entries);
+        self.sort();
+    }
+
+    /// Remove one entry equal to each of `entries`, so that of identical
+    /// entries only as many go as are given. Returns how many were removed.
+    pub fn remove(&mut self, entries: &[Entry]) -> usize {
+        let before = self.entries.len();
+        let mut pending = entries.to_vec();
+        self.entries
+            .retain(|entry| take_equal(&mut pending, entry).is_none());
+        before - self.entries.len()
+    }
+
+    /// Move one entry equal to each of `entries` to `time`, like `remove`
+    /// does, keeping the journal sorted. Returns how many were moved.
+    pub fn change_time(&mut self, entries: &[Entry], time: NaiveDateTime) -> usize {
+        let mut pending = entries.to_vec();
+        let mut changed = 0;
+        for entry in &mut self.entries {
+            if take_equal(&mut pending, entry).is_some() {
+                entry.time = time;
+                changed += 1;
+            }
+        }
+        self.sort();
+        changed
+    }
+
+    /// The whole journal the way it is stored in a plain text file. Entries
+    /// that are unchanged since `read` are written byte for byte as they were,
+    /// and so are the blank lines between those that are still next to each
+    /// other.
+    pub fn format(&self, timeformat: &str) -> String {
+        let mut sources = self.sources.iter().enumerate().collect::<Vec<_>>();
+        let mut out = self.preamble.clone();
+        // the index of the source written last, if the last entry had one
+        let mut last: Option<usize> = None;
+        for (index, entry) in self.entries.iter().enumerate() {
+            let source = sources
+                .iter()
+                .position(|(_, source)| source.entry == *entry)
+                .map(|position| sources.remove(position));
+            out.push_str(match (last, source) {
+                (Some(last), Some((next, _))) if next == last + 1 => &self.sources[last].gap,
+                // a preamble read before an entry already ends in a line break
+                _ if index == 0 && (out.is_empty() || out.ends_with('\n')) => "",
+                _ => separator(out.as_bytes()),
+            });
+            match source {
+                Some((next, source)) => {
+                    out.push_str(&source.text);
+                    last = Some(next);
+                }
+                None => {
+                    out.push_str(&entry.format(timeformat));
+                    last = None;
+                }
+            }
+        }
+        if let Some(last) = last.filter(|&last| last + 1 == self.sources.len()) {
+            out.push_str(&self.sources[last].gap);
+        }
+        out
+    }
+
+    /// Append a single entry to the end of a plain text journal, leaving the
+    /// existing contents untouched apart from the separating blank line.
+    pub fn append_to_file(path: &Path, entry: &Entry, timeformat: &str) -> io::Result<()> {
+        let mut file = OpenOptions::new()
+            .read(true)
+            .append(true)
+            .create(true)
+            .open(path)?;
+        let len = file.seek(SeekFrom::End(0))?;
+        let mut tail = [0u8; 2];
+        let tail = if len >= 2 {
+            file.seek(SeekFrom::End(-2))?;
+            file.read_exact(&mut tail)?;
+            &tail[..]
+        } else if len == 1 {
+            file.seek(SeekFrom::End(-1))?;
+            file.read_exact(&mut tail[..1])?;
+            &tail[..1]
+        } else {
+            &tail[..0]
+        };
+        write!(file, "{}{}", separator(tail), entry.format(timeformat))
+    }
+
+    /// Append a single entry to a journal that is rewritten as a whole, such
+    /// as an encrypted one.
+    pub fn append_to_text(text: &mut String, entry: &Entry, timeformat: &str) {
+        text.push_str(separator(text.as_bytes()));
+        text.push_str(&entry.format(timeformat));
+    }
+}
+
+/// Everything in the journal `text` that looks wrong: text before the first
+/// entry, and lines in entries that look like headers with a time that
+/// doesn't parse.
+pub fn check(name: &str, text: &str, timeformat: &str) -> Vec<ParseError> {
+    let mut problems = leading_problem(name, text, timeformat)
+        .into_iter()
+        .collect::<Vec<_>>();
+    let mut in_entry = false;
+    for (index, line) in text.lines().enumerate() {
+        match Entry::header_problem(line, timeformat) {
+            None => in_entry = true,
+            Some((JrnlErrorKind::InvalidTimestamp, span))
+                if in_entry && line[span.clone()].contains(|c: char| c.is_ascii_digit()) =>
+            {
+                problems.push(ParseError {
+                    kind: JrnlErrorKind::InvalidTimestamp,
+                    journal: name.to_owned(),
+                    path: None,
+                    line: index + 1,
+                    text: line.to_owned(),
+                    span,
+                    note: Some("kept as part of the entry above".to_owned()),
+                });
+            }
+            Some(_) => (),
+        }
+    }
+    problems
+}
+
+/// The problem with the text before the first entry of a journal, if there
+/// is any.
+fn leading_problem(name: &str, text: &str, timeformat: &str) -> Option<ParseError> {
+    let (index, line) = text
+        .lines()
+        .enumerate()
+        .find(|(_, line)| !line.trim().is_empty())?;
+    let (kind, span) = Entry::header_problem(line, timeformat)?;
+    Some(ParseError {
+        kind,
+        journal: name.to_owned(),
+        path: None,
+        line: index + 1,
+        text: line.to_owned(),
+        span,
+        note: Some("text before the first entry isn't shown, but is kept".to_owned()),
+    })
+}
+
+/// Take an entry equal to `entry` out of `entries`, if there is one.
+fn take_equal(entries: &mut Vec<Entry>, entry: &Entry) -> Option<Entry> {
+    let index = entries.iter().position(|other| other == entry)?;
+    Some(entries.swap_remove(index))
+}
+
+/// What goes between the existing journal contents ending in `tail` and a new
+/// entry: exactly one blank line.
+fn separator(tail: &[u8]) -> &'static str {
+    match tail {
+        [] | [.., b'\n', b'\n'] => "",
+        [.., b'\n'] => "\n",
+        _ => "\n\n",
+    }
+}
+
+/// The plain text of the journal file at `path`, decrypted with `password` if
+/// the file is encrypted. Given a password, header-less Python jrnl 1.x
+/// journals count as encrypted too.
+pub fn read_text(path: &Path, password: Option<&str>) -> Result<String> {
+    let data = fs::read(path)?;
+    let password = match password {
+        Some(password) if crypto::may_be_encrypted(&data) => password,
+        _ if !crypto::is_encrypted(&data) => return Ok(String::from_utf8(data)?),
+        _ => Err(JrnlError::new(JrnlErrorKind::WrongPassword))?,
+    };
+    Ok(String::from_utf8(crypto::decrypt(&data, password)?)?)
+}
+
+/// The plain text of the journal file at `path`, prompting for the password if
+/// it is encrypted and `store` doesn't know it. The password is returned too
+/// so the journal can be written back encrypted. Only with `headerless` are
+/// Python jrnl 1.x journals, which have no header, taken to be encrypted.
+pub fn unlock(
+    path: &Path,
+    journal_name: &str,
+    store: Option<&dyn CredentialStore>,
+    headerless: bool,
+) -> Result<(String, Option<String>)> {
+    let data = fs::read(path)?;
+    let encrypted = match headerless {
+        true => crypto::may_be_encrypted(&data),
+        false => crypto::is_encrypted(&data),
+    };
+    if !encrypted {
+        return Ok((read_text(path, None)?, None));
+    }
+    if let Some(store) = store {
+        if let Some(password) = store.get(journal_name)? {
+            match read_text(path, Some(&password)) {
+                Ok(text) => return Ok((text, Some(password))),
+                // the password was changed elsewhere
+                Err(err) if is_wrong_password(err.as_ref()) => store.delete(journal_name)?,
+                Err(err) => return Err(err),
+            };
+        }
+    }
+    for attempt in 1..=PASSWORD_ATTEMPTS {
+        let password = crypto::prompt_password("Password: ")?;
+        match read_text(path, Some(&password)) {
+            Ok(text) => {
+                if let Some(store) = store {
+                    store.set(journal_name, &password)?;
+                }
+                return Ok((text, Some(password)));
+            }
+            Err(err) if is_wrong_password(err.as_ref()) && attempt < PASSWORD_ATTEMPTS => {
+                eprintln!("Wrong password, try again.")
+            }
+            Err(err) => return Err(err),
+        }
+    }
+    unreachable!("the last attempt always returns")
+}
+
+fn is_wrong_password(err: &(dyn std::error::Error + 'static)) -> bool {
+    err.downcast_ref::<JrnlError>()
+        .is_some_and(|err| err.kind() == JrnlErrorKind::WrongPassword)
+}
+
+/// Replace the journal file at `path` with `text`, encrypted with `password`
+/// if given.
+pub fn write_text(path: &Path, text: &str, password: Option<&str>) -> Result<()> {
+    match password {
+        Some(password) => write_atomic(path, &crypto::encrypt(text.as_bytes(), password)?)?,
+        None => write_atomic(path, text.as_bytes())?,
+    }
+    Ok(())
+}
+
+/// Replace the contents of `path` by writing a temporary file next to it and
+/// renaming it into place, so readers never see a half-written file.
+pub fn write_atomic(path: &Path, contents: &[u8]) -> io::Result<()> {
+    let dir = match path.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent,
+        _ => Path::new("."),
+    };
+    let mut file = NamedTempFile::new_in(dir)?;
+    file.write_all(contents)?;
+    file.as_file().sync_all()?;
+    file.persist(path).map_err(|err| err.error)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_separator() {
+        assert_eq!(separator(b""), "");
+        assert_eq!(separator(b"text\n\n"), "");
+        assert_eq!(separator(b"text\n"), "\n");
+        assert_eq!(separator(b"text"), "\n\n");
+    }
+
+    #[test]
+    fn test_merge() {
+        let text = "[2023-01-12 08:00:00 AM] First.\n\n[2023-01-14 08:00:00 AM] Third.\nBody\n";
+        let mut journal = Journal::parse("default", text, "%F %r").unwrap();
+        let incoming = Journal::parse(
+            "import",
+            "[2023-01-13 08:00:00 AM] Second.\n\n\
+             [2023-01-14 08:00:00 AM] Third.\nBody\n\n\
+             [2023-01-14 08:00:00 AM] Third.\nOther body\n",
+            "%F %r",
+        )
+        .unwrap();
+        assert_eq!(journal.merge(incoming.entries), (2, 1));
+        let titles = journal
+            .entries()
+            .iter()
+            .map(|e| e.title())
+            .collect::<Vec<_>>();
+        assert_eq!(titles, ["First.", "Second.", "Third.", "Third."]);
+        assert_eq!(
+            journal.format("%F %r"),
+            "[2023-01-12 08:00:00 AM] First.\n\n\
+             [2023-01-13 08:00:00 AM] Second.\n\n\
+             [2023-01-14 08:00:00 AM] Third.\nBody\n\n\
+             [2023-01-14 08:00:00 AM] Third.\nOther body\n"
+        );
+    }
+
+    #[test]
+    fn test_remove_takes_only_given_occurrences() {
+        let text = "[2023-01-12 08:00:00 AM] Twice.\n\n[2023-01-12 08:00:00 AM] Twice.\n";
+        let mut journal = Journal::parse("default", text, "%F %r").unwrap();
+        let twice = journal.entries()[0].clone();
+        assert_eq!(journal.remove(std::slice::from_ref(&twice)), 1);
+        assert_eq!(journal.entries(), std::slice::from_ref(&twice));
+        // as when editing one of them: an unselected identical entry stays
+        journal.insert(vec![twice.clone()]);
+        assert_eq!(journal.entries().len(), 2);
+        assert_eq!(journal.remove(&[twice.clone(), twice.clone(), twice]), 2);
+        assert!(journal.entries().is_empty());
+    }
+
+    #[test]
+    fn test_change_time() {
+        let text = "[2023-01-12 08:00:00 AM] First.\n\n[2023-01-14 08:00:00 AM] Second.\n";
+        let mut journal = Journal::parse("default", text, "%F %r").unwrap();
+        let second = journal.entries()[1].clone();
+        let time = NaiveDateTime::parse_from_str("2023-01-10 20:00", "%F %R").unwrap();
+        assert_eq!(journal.change_time(&[second], time), 1);
+        assert_eq!(
+            journal.format("%F %r"),
+            "[2023-01-10 08:00:00 PM] Second.\n\n[2023-01-12 08:00:00 AM] First.\n"
+        );
+        // only the selected one of identical entries moves
+        let text = "[2023-01-12 08:00:00 AM] Twice.\n\n[2023-01-12 08:00:00 AM] Twice.\n";
+        let mut journal = Journal::parse("default", text, "%F %r").unwrap();
+        let twice = journal.entries()[0].clone();
+        assert_eq!(journal.change_time(&[twice], time), 1);
+        assert_eq!(
+            journal.format("%F %r"),
+            "[2023-01-10 08:00:00 PM] Twice.\n\n[2023-01-12 08:00:00 AM] Twice.\n"
+        );
+    }
+
+    #[test]
+    fn test_parse_rejects_garbage() {
+        let parse = |text| Journal::parse("default", text, "%F %r");
+        assert!(parse("").unwrap().entries().is_empty());
+        assert!(parse("not a journal\n").is_err());
+        assert!(parse("[yesterday] Nope.\n").is_err());
+    }
+
+    #[test]
+    fn test_read_is_lossless() {
+        let text = "Written by hand.\n[not a date] either\n\n\
+                    [2023-01-12 08:00:00 AM] First.\n\
+                    [x] done\n[ ] not done\n\n\
+                    [2023-01-13 08:00:00 AM] Second.\n";
+        let journal = Journal::read("default", text, "%F %r");
+        let titles = journal
+            .entries()
+            .iter()
+            .map(|e| e.title())
+            .collect::<Vec<_>>();
+        assert_eq!(titles, ["First.", "Second."]);
+        assert_eq!(journal.entries()[0].text(), "[x] done\n[ ] not done");
+        assert_eq!(
+            journal.preamble(),
+            "Written by hand.\n[not a date] either\n\n"
+        );
+        assert_eq!(journal.format("%F %r"), text);
+
+        // leading blank lines are kept too, as is a preamble without entries
+        let text = "\n\n  \n[2023-01-12 08:00:00 AM] First.\n";
+        assert_eq!(
+            Journal::read("default", text, "%F %r").format("%F %r"),
+            text
+        );
+        let text = "\nJust notes.";
+        assert_eq!(
+            Journal::read("default", text, "%F %r").format("%F %r"),
+            text
+        );
+    }
+
+    #[test]
+    fn test_unchanged_entries_are_kept_byte_for_byte() {
+        // an older time format, a header whose time doesn't parse, stray
+        // whitespace and no blank line between entries, which rendering the
+        // entries again would all change
+        let text = "[2023-01-12 08:00] First.  \r\n\
+                    Body\r\n\
+                    [2023-02-30 08:00:00 AM] Typo.\r\n\
+                    [2023-01-13 08:00:00 AM] Second.\n\n\n\
+                    [2023-01-14 08:00:00 AM] Third.\n   \n";
+        let mut journal = Journal::read("default", text, "%F %r");
+        assert_eq!(journal.entries().len(), 3);
+        assert_eq!(journal.format("%F %r"), text);
+
+        let second = journal.entries()[1].clone();
+        assert_eq!(journal.remove(&[second]), 1);
+        assert_eq!(
+            journal.format("%F %r"),
+            "[2023-01-12 08:00] First.  \r\n\
+             Body\r\n\
+             [2023-02-30 08:00:00 AM] Typo.\r\n\n\
+             [2023-01-14 08:00:00 AM] Third.\n   \n"
+        );
+
+        // a changed entry is written anew, the others as they were
+        let first = journal.entries()[0].clone();
+        let time = NaiveDateTime::parse_from_str("2023-01-15 09:00", "%F %R").unwrap();
+        journal.change_time(&[first], time);
+        assert_eq!(
+            journal.format("%F %r"),
+            "[2023-01-14 08:00:00 AM] Third.\n\n\
+             [2023-01-15 09:00:00 AM] First.  \n\
+             Body\n\
+             [2023-02-30 08:00:00 AM] Typo.\n"
+        );
+    }
+
+    #[test]
+    fn test_check() {
+        let text = "\nNotes\n\n\
+                    [2023-01-12 08:00:00 AM] First.\n\
+                    [x] done\n\
+                    [2023-02-30 08:00:00 AM] Typo.\n";
+        let problems = check("default", text, "%F %r");
+        let found = problems
+            .iter()
+            .map(|p| (p.kind, p.line, &p.text[p.span.clone()]))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            found,
+            [
+                (JrnlErrorKind::InvalidTitleLine, 2, "Notes"),
+                (JrnlErrorKind::InvalidTimestamp, 6, "2023-02-30 08:00:00 AM"),
+            ]
+        );
+        assert_eq!(
+            problems[1].clone().in_file(Path::new("j.txt")).to_string(),
+            "error: failed to parse entry time\n\
+             \x20--> j.txt:6 (journal 'default')\n\
+             \x20 |\n\
+             6 | [2023-02-30 08:00:00 AM] Typo.\n\
+             \x20 |  ^^^^^^^^^^^^^^^^^^^^^^\n\
+             \x20 = note: kept as part of the entry above"
+        );
+
+        let err = Journal::parse("default", text, "%F %r").unwrap_err();
+        assert!(err.to_string().contains("line 2 (journal 'default')"));
+    }
 
-        Self { entries, name }
+    #[test]
+    fn test_plain_text_round_trip() {
+        let file = NamedTempFile::new().unwrap();
+        write_text(file.path(), "[2023-01-12 08:51:57 AM] Hi.\n", None).unwrap();
+        assert_eq!(
+            read_text(file.path(), None).unwrap(),
+            "[2023-01-12 08:51:57 AM] Hi.\n"
+        );
     }
 }
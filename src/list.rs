@@ -1,12 +1,15 @@
 // use std::path::PathBuf;
 
 use crate::cli::{ListArgs, ListFormat};
+use crate::error::JrnlError;
 use crate::settings::{JournalConfigs, Settings};
 use serde_json::json;
 use serde_yml::Value;
 
-pub fn list(args: &ListArgs, settings: &Settings, config_file: &str) {
-    let journal_configs = settings.get_journals();
+type Result<T> = std::result::Result<T, Box<dyn std::error::Error>>;
+
+pub fn list(args: &ListArgs, settings: &Settings, config_file: &str) -> Result<()> {
+    let journal_configs = settings.get_journals()?;
 
     match args.format {
         Some(ListFormat::Json) => list_json(&journal_configs, config_file),
@@ -15,34 +18,41 @@ pub fn list(args: &ListArgs, settings: &Settings, config_file: &str) {
     }
 }
 
-fn list_json(journal_map: &JournalConfigs, config_file: &str) {
+fn list_json(journal_map: &JournalConfigs, config_file: &str) -> Result<()> {
     let mut j = json!({
         "config_path": config_file,
     });
-    j.as_object_mut()
-        .unwrap()
-        .append(json!(journal_map).as_object_mut().unwrap());
+    if let (Some(j), serde_json::Value::Object(mut journals)) =
+        (j.as_object_mut(), serde_json::to_value(journal_map)?)
+    {
+        j.append(&mut journals);
+    }
 
-    println!("{}", j.to_string());
+    println!("{}", j);
+    Ok(())
 }
 
-fn list_yaml(journal_map: &JournalConfigs, config_file: &str) {
+fn list_yaml(journal_map: &JournalConfigs, config_file: &str) -> Result<()> {
     let mut map = serde_yml::Mapping::new();
-    let journal_value = serde_yml::to_value(journal_map).expect("error serializing this struct");
+    let journal_value = serde_yml::to_value(journal_map)?;
     map.insert(Value::String("config_path".into()), config_file.into());
     if let Value::Tagged(val) = journal_value {
-        map.insert(Value::String(val.tag.string.into()), val.value);
+        map.insert(Value::String(val.tag.string), val.value);
     }
     let value = serde_yml::Value::Mapping(map);
-    println!("{}", serde_yml::to_string(&value).unwrap())
+    println!("{}", serde_yml::to_string(&value)?);
+    Ok(())
 }
 
-fn list_plain(journal_map: &JournalConfigs, config_file: &str) {
+fn list_plain(journal_map: &JournalConfigs, config_file: &str) -> Result<()> {
     println!("Journals defined in config ({config_file})");
     if let JournalConfigs::Journals(map) = journal_map {
         for (name, cfg) in map {
-            let file = cfg.journal_file().expect("unexpected path to journal");
+            let file = cfg
+                .journal_file()
+                .map_err(|err| JrnlError::new(err.kind()).context(format!("journal '{name}'")))?;
             println!(" * {name} -> {file}");
         }
     }
+    Ok(())
 }
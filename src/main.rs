@@ -1,45 +1,97 @@
+use chrono::{Local, Timelike};
 use clap::Parser;
 use config::{Config, File, FileFormat};
 use directories::ProjectDirs;
 
 use std::fs;
+use std::io::IsTerminal;
 use std::path::Path;
+use std::process::ExitCode;
+mod change_time;
+mod check;
 mod cli;
+mod compose;
+mod credentials;
+mod crypto;
+mod datetime;
 mod decrypt;
+mod delete;
+mod edit;
+mod editor;
 mod encrypt;
 mod entry;
 mod error;
+mod export;
+mod filter;
+mod format;
 mod import;
 mod journal;
 mod list;
+mod migrate;
+mod prompt;
 mod settings;
+mod store;
 
 use cli::{Cli, Commands};
+use error::{JrnlError, JrnlErrorKind};
 use settings::Settings;
 
-fn handle_subcommand(cli: &Cli, settings: &Settings, config_file: &str) {
+fn handle_subcommand(
+    cli: &Cli,
+    settings: &Settings,
+    config_file: &str,
+    journal_name: &str,
+    journal_file: &Path,
+) -> Result<(), Box<dyn std::error::Error>> {
     match cli.command.clone() {
-        Some(Commands::Encrypt) => encrypt::encrypt(),
-        Some(Commands::Decrypt) => decrypt::decrypt(),
-        Some(Commands::List(args)) => list::list(&args, settings, config_file),
-        Some(Commands::Import(args)) => import::import(&args),
+        Some(Commands::Encrypt) => {
+            encrypt::encrypt(settings, journal_name, journal_file, config_file)?
+        }
+        Some(Commands::Decrypt(args)) => decrypt::decrypt(
+            settings,
+            journal_name,
+            journal_file,
+            config_file,
+            args.file.as_deref(),
+        )?,
+        Some(Commands::ForgetPassword) => credentials::forget(settings, journal_name)?,
+        Some(Commands::List(args)) => list::list(&args, settings, config_file)?,
+        Some(Commands::Import(args)) => {
+            import::import(&args, settings, journal_name, journal_file)?
+        }
+        Some(Commands::Check) => check::check(settings, journal_name, journal_file)?,
+        Some(Commands::Migrate(args)) => {
+            migrate::migrate(&args, settings, journal_name, journal_file)?
+        }
         None => (),
     }
+    Ok(())
+}
+
+/// Failures are reported on stderr, with an exit status by kind of error; see
+/// the `EXIT_*` constants in `error`.
+fn main() -> ExitCode {
+    match run() {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(err) => {
+            eprintln!("{}", error::report(err.as_ref()));
+            ExitCode::from(error::exit_code(err.as_ref()))
+        }
+    }
 }
 
-fn main() -> Result<(), Box<dyn std::error::Error>> {
+fn run() -> Result<(), Box<dyn std::error::Error>> {
     let mut cli = Cli::parse();
 
-    let project_base = ProjectDirs::from("", "", "jrnl").unwrap();
-    let conffile = if cli.config_file.clone().is_some() {
-        cli.config_file.clone().unwrap()
-    } else {
-        project_base
+    let project_base = ProjectDirs::from("", "", "jrnl")
+        .ok_or_else(|| JrnlError::new(JrnlErrorKind::NoHomeDirectory))?;
+    let conffile = match cli.config_file.clone() {
+        Some(conffile) => conffile,
+        None => project_base
             .config_local_dir()
             .join("jrnl.yaml")
-            .to_str()
-            .unwrap()
-            .to_owned()
+            .to_string_lossy()
+            .into_owned(),
     };
     let settings = match Settings::configure(&conffile, cli.clone()) {
         Ok(s) => s,
@@ -47,9 +99,8 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
             let journal_file = project_base
                 .data_local_dir()
                 .join("journal.txt")
-                .to_str()
-                .unwrap()
-                .to_owned();
+                .to_string_lossy()
+                .into_owned();
             let s = Settings::default().with_journal("default", &journal_file);
             fs::create_dir_all(project_base.config_local_dir())?;
             let yaml = serde_yml::to_string(&s)?;
@@ -60,13 +111,15 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
                 .build()?
                 .try_deserialize()?
         }
-        err => err?,
+        Err(err) => Err(JrnlError::new(JrnlErrorKind::InvalidConfig)
+            .context(format!("failed to load config file '{conffile}'"))
+            .source(err))?,
     };
 
     let mut journal_name = cli.clone().journal.unwrap_or("default".to_owned());
     let journal_file = match settings.journal_file(&journal_name) {
         Ok(journal_file) => Path::new(journal_file),
-        Err(error::JrnlError(error::JrnlErrorKind::MissingJournalConfig)) => {
+        Err(err) if err.kind() == JrnlErrorKind::MissingJournalConfig => {
             cli.entry.insert(0, journal_name);
             cli.journal = None;
             journal_name = "default".to_owned();
@@ -75,17 +128,89 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
         }
         err => Path::new(err?),
     };
-    if !fs::exists(journal_file)? {
-        let parent = journal_file.parent().unwrap();
-        if !fs::exists(parent)? {
-            fs::create_dir_all(parent)?;
-        }
-        fs::File::create(journal_file)?;
+    store::create(journal_file).map_err(|err| {
+        JrnlError::new(JrnlErrorKind::Io)
+            .context(format!(
+                "failed to create journal '{}'",
+                journal_file.display()
+            ))
+            .source(err.to_string())
+    })?;
+
+    if cli.command.is_some() {
+        return handle_subcommand(&cli, &settings, &conffile, &journal_name, journal_file);
+    }
+    let tagsymbols = settings.tagsymbols(&journal_name)?;
+    if cli.search.is_empty() && !filter::all_tags(&cli.entry, &tagsymbols) {
+        return match cli.entry.is_empty() {
+            true => compose::compose_in_editor(&settings, &journal_name, journal_file),
+            false => compose::compose(&cli.entry, &settings, &journal_name, journal_file),
+        };
     }
 
-    handle_subcommand(&cli, &settings, &conffile.clone());
-    let mut file = fs::File::open(journal_file).expect("File open failed");
-    let _journal = journal::Journal::from_file(&journal_name, &mut file);
+    let tags = filter::tags_in(&cli.entry, &tagsymbols);
+    let now = Local::now().naive_local();
+    let default_time = compose::default_time(&settings, &journal_name)?;
+    let filter = filter::Filter::new(&cli.search, &tags, &tagsymbols, now, default_time)?;
+    let new_time = match &cli.search.change_time {
+        Some(when) => Some(
+            datetime::parse(when, now.with_nanosecond(0).unwrap_or(now), default_time).ok_or_else(
+                || {
+                    JrnlError::new(JrnlErrorKind::InvalidArgument)
+                        .context(format!("could not parse date '{when}'"))
+                },
+            )?,
+        ),
+        None => None,
+    };
+    let mut store = store::open(&settings, &journal_name, journal_file)?;
+    let include = |date| filter.matches_date(date);
+    // only entries with the text can match, which some stores look up quickly
+    let indexed = match &cli.search.contains {
+        Some(text) if tags.is_empty() || cli.search.and == Some(true) => {
+            store.load_containing(text, &include)?
+        }
+        _ => None,
+    };
+    let journal = match indexed {
+        Some(journal) => journal,
+        None => store.load(&include)?,
+    };
+    let display = match cli.search.display_format() {
+        Some(display) => display,
+        None => settings.display_format(&journal_name)?,
+    };
+    let color = cli.search.file.is_none() && std::io::stdout().is_terminal();
+    let options = format::FormatOptions::from_settings(&settings, &journal_name, color)?;
+    let formatter = format::formatter(display, options);
+    let entries = filter.apply(&journal);
+    if cli.search.edit == Some(true) {
+        let entries = entries.into_iter().cloned().collect::<Vec<_>>();
+        return edit::edit(&entries, store.as_mut(), &settings, &journal_name);
+    }
+    if let Some(time) = new_time {
+        return change_time::change_time(
+            &entries,
+            time,
+            store.as_mut(),
+            formatter.as_ref(),
+            cli.search.yes == Some(true),
+            cli.search.dry_run == Some(true),
+        );
+    }
+    if cli.search.delete == Some(true) {
+        return delete::delete(
+            &entries,
+            store.as_mut(),
+            formatter.as_ref(),
+            cli.search.yes == Some(true),
+            cli.search.dry_run == Some(true),
+        );
+    }
+    match &cli.search.file {
+        Some(path) => export::export(formatter.as_ref(), &entries, path)?,
+        None => print!("{}", formatter.format_entries(&entries)),
+    }
 
     Ok(())
 }
@@ -0,0 +1,93 @@
+use std::path::Path;
+
+use crate::cli::MigrateArgs;
+use crate::settings::Settings;
+use crate::store;
+
+type Result<T> = std::result::Result<T, Box<dyn std::error::Error>>;
+
+/// Copy every entry of the journal to another journal path, such as an
+/// `sqlite://` database or a plain text file, skipping those already there.
+/// The journal itself and the config file are left untouched. The copy isn't
+/// encrypted, even if the journal is, unless the target file already is.
+pub fn migrate(
+    args: &MigrateArgs,
+    settings: &Settings,
+    journal_name: &str,
+    journal_file: &Path,
+) -> Result<()> {
+    let target = Path::new(&args.to);
+    if target == journal_file {
+        Err(format!(
+            "journal '{journal_name}' is already at {}",
+            args.to
+        ))?
+    }
+    let entries = store::open(settings, journal_name, journal_file)?
+        .load(&|_| true)?
+        .into_entries();
+
+    store::create(target)?;
+    // `encrypt` applies to the journal, and sqlite and folder journals can't be
+    let mut store = store::open_with(settings, journal_name, target, false)?;
+    let mut journal = store.load(&|_| true)?;
+    let (added, skipped) = journal.merge(entries);
+    if added > 0 {
+        store.replace(&journal)?;
+    }
+    eprintln!(
+        "[{added} entries migrated to {}, {skipped} skipped as duplicates]",
+        args.to
+    );
+    eprintln!(
+        "[Set journals.{journal_name} to '{}' in your config file to use it]",
+        args.to
+    );
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    fn settings(journal_file: &Path) -> Settings {
+        let yaml = format!(
+            "encrypt: true\ntimeformat: '%F %r'\njournals:\n  default: {}\n",
+            journal_file.display()
+        );
+        serde_yml::from_str(&yaml).unwrap()
+    }
+
+    #[test]
+    fn test_migrate_from_encrypted_journal() {
+        // set to be encrypted, but not yet, so that no password is needed
+        let dir = tempfile::tempdir().unwrap();
+        let journal_file = dir.path().join("journal.txt");
+        let text = "[2023-01-12 08:00:00 AM] First.\n";
+        fs::write(&journal_file, text).unwrap();
+        let target = dir.path().join("copy.txt");
+        let args = MigrateArgs {
+            to: target.display().to_string(),
+        };
+
+        migrate(&args, &settings(&journal_file), "default", &journal_file).unwrap();
+        assert_eq!(fs::read_to_string(&target).unwrap(), text);
+    }
+
+    #[cfg(feature = "sqlite")]
+    #[test]
+    fn test_migrate_from_encrypted_journal_to_sqlite() {
+        let dir = tempfile::tempdir().unwrap();
+        let journal_file = dir.path().join("journal.txt");
+        fs::write(&journal_file, "[2023-01-12 08:00:00 AM] First.\n").unwrap();
+        let args = MigrateArgs {
+            to: format!("sqlite://{}", dir.path().join("journal.db").display()),
+        };
+
+        let settings = settings(&journal_file);
+        migrate(&args, &settings, "default", &journal_file).unwrap();
+        let mut store = store::open_with(&settings, "default", Path::new(&args.to), false).unwrap();
+        assert_eq!(store.load(&|_| true).unwrap().entries().len(), 1);
+    }
+}
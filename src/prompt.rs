@@ -0,0 +1,88 @@
+use std::io::{BufRead, Write};
+
+use crate::entry::Entry;
+use crate::error::{JrnlError, JrnlErrorKind};
+use crate::format::Formatter;
+
+type Result<T> = std::result::Result<T, Box<dyn std::error::Error>>;
+
+/// The `entries` the user agrees to `question` for, asking about each in turn
+/// after showing it with `formatter`. Answers are read from `input`: `y` or
+/// `n` for a single entry, `a` for this and all remaining ones and `q` to give
+/// up without changing anything, as does running out of input.
+pub fn confirm_each<'e>(
+    entries: &[&'e Entry],
+    question: &str,
+    formatter: &dyn Formatter,
+    input: &mut dyn BufRead,
+) -> Result<Vec<&'e Entry>> {
+    let mut confirmed = vec![];
+    for (index, &entry) in entries.iter().enumerate() {
+        eprintln!("{}", formatter.format_entries(&[entry]).trim_end());
+        loop {
+            eprint!("{question} [y/n/a/q] ");
+            std::io::stderr().flush()?;
+            let mut answer = String::new();
+            if input.read_line(&mut answer)? == 0 {
+                eprintln!();
+                answer = "q".to_owned();
+            }
+            match answer.trim().to_lowercase().as_str() {
+                "y" | "yes" => confirmed.push(entry),
+                "n" | "no" => (),
+                "a" | "all" => {
+                    confirmed.extend_from_slice(&entries[index..]);
+                    return Ok(confirmed);
+                }
+                "q" | "quit" => {
+                    Err(JrnlError::new(JrnlErrorKind::Aborted).context("nothing changed"))?
+                }
+                _ => continue,
+            }
+            break;
+        }
+    }
+    Ok(confirmed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::format::{self, FormatOptions};
+    use crate::settings::DisplayConfig;
+    use chrono::NaiveDate;
+
+    #[test]
+    fn test_confirm_each() {
+        let entries = (1..=4)
+            .map(|day| {
+                let time = NaiveDate::from_ymd_opt(2024, 5, day)
+                    .unwrap()
+                    .and_hms_opt(8, 0, 0)
+                    .unwrap();
+                Entry::new(time, &format!("day {day}"), "")
+            })
+            .collect::<Vec<_>>();
+        let entries = entries.iter().collect::<Vec<_>>();
+        let options = FormatOptions::for_test("%F %R");
+        let formatter = format::formatter(DisplayConfig::Short, options);
+        let confirm = |answers: &str| {
+            confirm_each(
+                &entries,
+                "Delete?",
+                formatter.as_ref(),
+                &mut answers.as_bytes(),
+            )
+            .map(|confirmed| confirmed.iter().map(|e| e.title()).collect::<Vec<_>>())
+        };
+
+        assert_eq!(confirm("y\nn\nwhat\nY\nn\n").unwrap(), ["day 1", "day 3"]);
+        assert_eq!(confirm("n\na\n").unwrap(), ["day 2", "day 3", "day 4"]);
+        let err = confirm("y\nq\n").unwrap_err();
+        assert_eq!(
+            crate::error::kind_of(err.as_ref()),
+            Some(JrnlErrorKind::Aborted)
+        );
+        assert!(confirm("y\n").is_err());
+    }
+}
@@ -42,39 +42,29 @@ impl<'a> Settings {
     pub fn journal_file(&'a self, journal_name: &str) -> Result<&'a str> {
         self.journal_settings(journal_name).map(|(_, f)| f)
     }
-    pub fn get_journals(&'a self) -> JournalConfigs {
-        let journal_map = self
-            .config
-            .journal_config
-            .clone()
-            .map(|configs| match configs {
-                JournalConfigs::Journals(journals) => journals
-                    .iter()
-                    .map(|(name, config)| match config {
-                        JournalConfig::Standard(path) => {
-                            (name.clone(), JournalConfig::Standard(path.to_string()))
-                        }
-                        JournalConfig::Override(CommonConfig {
-                            journal_config: Some(JournalConfigs::Journal(path)),
-                            ..
-                        }) => (
-                            name.clone(),
-                            JournalConfig::Override(
-                                CommonConfig::new()
-                                    .journal_config(JournalConfigs::Journal(path.to_string())),
-                            ),
+    pub fn get_journals(&'a self) -> Result<JournalConfigs> {
+        let journal_map = match self.config.journal_config.clone() {
+            Some(JournalConfigs::Journals(journals)) => journals
+                .iter()
+                .map(|(name, config)| {
+                    let path = config.journal_file()?.to_owned();
+                    let config = match config {
+                        JournalConfig::Standard(_) => JournalConfig::Standard(path),
+                        JournalConfig::Override(_) => JournalConfig::Override(
+                            CommonConfig::new().journal_config(JournalConfigs::Journal(path)),
                         ),
-                        _ => unreachable!("invalid configuration"),
-                    })
-                    .collect(),
-                JournalConfigs::Journal(path) => {
-                    let mut map = IndexMap::new();
-                    map.insert(String::new(), JournalConfig::Standard(path));
-                    map
-                }
-            })
-            .unwrap();
-        JournalConfigs::Journals(journal_map)
+                    };
+                    Ok((name.clone(), config))
+                })
+                .collect::<Result<_>>()?,
+            Some(JournalConfigs::Journal(path)) => {
+                let mut map = IndexMap::new();
+                map.insert(String::new(), JournalConfig::Standard(path));
+                map
+            }
+            None => Err(JrnlError::new(JrnlErrorKind::MissingJournalConfig))?,
+        };
+        Ok(JournalConfigs::Journals(journal_map))
     }
     fn journal_settings(&'a self, journal_name: &str) -> Result<(&'a CommonConfig, &'a str)> {
         self.config
@@ -90,10 +80,10 @@ impl<'a> Settings {
                             JournalConfig::Standard(_) => journal_file.map(|p| (&self.config, p)),
                         }
                     })
-                    .ok_or(JrnlError(JrnlErrorKind::MissingJournalConfig))?,
-                _ => Err(JrnlError(JrnlErrorKind::TopLevelJournalConfig))?,
+                    .ok_or(JrnlError::new(JrnlErrorKind::MissingJournalConfig))?,
+                _ => Err(JrnlError::new(JrnlErrorKind::TopLevelJournalConfig))?,
             })
-            .ok_or(JrnlError(JrnlErrorKind::MissingJournalConfig))?
+            .ok_or(JrnlError::new(JrnlErrorKind::MissingJournalConfig))?
     }
     pub fn default_hour(&self, journal_name: &str) -> Result<i8> {
         let (config, _) = self.journal_settings(journal_name)?;
@@ -126,7 +116,7 @@ impl<'a> Settings {
             .editor
             .clone()
             .or(self.config.editor.clone())
-            .ok_or(JrnlError(JrnlErrorKind::InvalidJrnlOverrideConfig))
+            .ok_or(JrnlError::new(JrnlErrorKind::InvalidJrnlOverrideConfig))
     }
     pub fn encrypt(&self, journal_name: &str) -> Result<bool> {
         let (config, _) = self.journal_settings(journal_name)?;
@@ -150,6 +140,13 @@ impl<'a> Settings {
         let (config, _) = self.journal_settings(journal_name)?;
         Ok(config.linewrap.or(self.config.linewrap).unwrap_or_default())
     }
+    pub fn password_store(&self, journal_name: &str) -> Result<PasswordStoreConfig> {
+        let (config, _) = self.journal_settings(journal_name)?;
+        Ok(config
+            .password_store
+            .or(self.config.password_store)
+            .unwrap_or_default())
+    }
     pub fn tagsymbols(&self, journal_name: &str) -> Result<String> {
         let (config, _) = self.journal_settings(journal_name)?;
         Ok(config
@@ -176,6 +173,120 @@ impl<'a> Settings {
     }
 }
 
+/// Set `encrypt` for a journal in the config file itself, leaving environment
+/// and command line overrides out of it. Only the lines of that journal
+/// change, so comments and the order of keys in the file are kept.
+pub fn set_journal_encrypt(
+    config_file: &str,
+    journal_name: &str,
+    encrypt: bool,
+) -> std::result::Result<(), Box<dyn std::error::Error>> {
+    let text = std::fs::read_to_string(config_file)?;
+    let text = with_journal_encrypt(&text, journal_name, encrypt)?;
+    crate::journal::write_atomic(std::path::Path::new(config_file), text.as_bytes())?;
+    Ok(())
+}
+
+/// The YAML config `text` with `encrypt` set for the journal `journal_name`,
+/// either by changing its `encrypt` line or by adding one, turning a journal
+/// given as just a path into one with its own settings if need be.
+fn with_journal_encrypt(text: &str, journal_name: &str, encrypt: bool) -> Result<String> {
+    let settings: Settings = serde_yml::from_str(text)
+        .map_err(|e| JrnlError::new(JrnlErrorKind::InvalidConfig).source(e))?;
+    settings.journal_settings(journal_name)?;
+    let cannot_edit = || {
+        JrnlError::new(JrnlErrorKind::InvalidConfig).context(format!(
+            "can't set 'encrypt: {encrypt}' for journal '{journal_name}', set it by hand"
+        ))
+    };
+
+    fn indent(line: &str) -> usize {
+        line.len() - line.trim_start().len()
+    }
+    fn key(line: &str) -> Option<(&str, &str)> {
+        let (key, value) = line.trim().split_once(':')?;
+        Some((key.trim_end().trim_matches(['"', '\'']), value.trim()))
+    }
+    // blank lines and comments don't end a block
+    fn is_content(line: &str) -> bool {
+        !line.trim().is_empty() && !line.trim_start().starts_with('#')
+    }
+
+    let lines = text.split_inclusive('\n').collect::<Vec<_>>();
+    // the lines indented under the line at `start`
+    let block = |start: usize| {
+        let end = lines[start + 1..]
+            .iter()
+            .position(|line| is_content(line) && indent(line) <= indent(lines[start]))
+            .map_or(lines.len(), |end| start + 1 + end);
+        start + 1..end
+    };
+    let child = |start: usize, name: &str| {
+        let children = block(start);
+        let level = lines[children.clone()]
+            .iter()
+            .find(|line| is_content(line))
+            .map(|line| indent(line))?;
+        children.into_iter().find(|&index| {
+            indent(lines[index]) == level && key(lines[index]).is_some_and(|(key, _)| key == name)
+        })
+    };
+
+    let journals = lines
+        .iter()
+        .position(|line| indent(line) == 0 && key(line).is_some_and(|(key, _)| key == "journals"))
+        .ok_or_else(cannot_edit)?;
+    let journal = child(journals, journal_name).ok_or_else(cannot_edit)?;
+    let line = lines[journal];
+    let newline = match &line[line.trim_end_matches(['\n', '\r']).len()..] {
+        "" => "\n",
+        newline => newline,
+    };
+    let (_, value) = key(line).ok_or_else(cannot_edit)?;
+    let mut edited = lines.clone();
+    let replacement;
+    match child(journal, "encrypt") {
+        Some(index) => {
+            let line = lines[index];
+            let (content, newline) = line.split_at(line.trim_end_matches(['\n', '\r']).len());
+            let comment = content.find(" #").map_or("", |start| &content[start..]);
+            replacement = format!(
+                "{}encrypt: {encrypt}{comment}{newline}",
+                &line[..indent(line)]
+            );
+            edited[index] = &replacement;
+        }
+        // a journal with its own settings
+        None if value.is_empty() || value.starts_with('#') => {
+            let level = lines[block(journal)]
+                .iter()
+                .find(|line| is_content(line))
+                .map(|line| indent(line))
+                .ok_or_else(cannot_edit)?;
+            replacement = format!("{}encrypt: {encrypt}{newline}", " ".repeat(level));
+            edited.insert(journal + 1, &replacement);
+        }
+        None if value.starts_with(['{', '[', '|', '>', '&', '*', '!']) => Err(cannot_edit())?,
+        // a journal given as just its path
+        None => {
+            let pad = &line[..indent(line)];
+            let level = " ".repeat(2 * indent(line));
+            let name = line.trim_start().split_once(':').ok_or_else(cannot_edit)?.0;
+            replacement = format!(
+                "{pad}{name}:{newline}{level}encrypt: {encrypt}{newline}{level}journal: {value}{newline}"
+            );
+            edited[journal] = &replacement;
+        }
+    }
+    let edited = edited.concat();
+
+    let settings: Settings = serde_yml::from_str(&edited).map_err(|_| cannot_edit())?;
+    match settings.encrypt(journal_name) {
+        Ok(set) if set == encrypt => Ok(edited),
+        _ => Err(cannot_edit()),
+    }
+}
+
 #[skip_serializing_none]
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct CommonConfig {
@@ -190,6 +301,7 @@ pub struct CommonConfig {
     #[serde(flatten)]
     journal_config: Option<JournalConfigs>,
     linewrap: Option<LineWrapConfig>,
+    password_store: Option<PasswordStoreConfig>,
     tagsymbols: Option<String>,
     template: Option<TemplateConfig>,
     timeformat: Option<String>,
@@ -227,6 +339,7 @@ impl CommonConfig {
             indent_character: None,
             journal_config: None,
             linewrap: None,
+            password_store: None,
             tagsymbols: None,
             template: None,
             timeformat: None,
@@ -299,6 +412,7 @@ impl Default for CommonConfig {
             indent_character: Some('|'),
             journal_config: None,
             linewrap: Some(LineWrapConfig::default()),
+            password_store: None,
             tagsymbols: Some("#@".to_owned()),
             template: Some(TemplateConfig::default()),
             timeformat: Some("%F %r".to_owned()),
@@ -322,7 +436,7 @@ impl JournalConfig {
                 {
                     Ok(journal_file)
                 } else {
-                    Err(JrnlError(JrnlErrorKind::InvalidJrnlOverrideConfig))
+                    Err(JrnlError::new(JrnlErrorKind::InvalidJrnlOverrideConfig))
                 }
             }
         }
@@ -362,6 +476,19 @@ impl Default for LineWrapConfig {
     }
 }
 
+/// Where the passwords of encrypted journals are remembered.
+#[derive(Debug, Default, Serialize, Deserialize, Copy, Clone, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum PasswordStoreConfig {
+    /// Ask every time.
+    #[default]
+    Prompt,
+    /// A file only the user can read, in jrnl's data directory.
+    File,
+    /// The desktop keyring, via the Secret Service API.
+    SecretService,
+}
+
 #[derive(Debug, Serialize, Deserialize, Copy, Clone)]
 pub struct ColorConfig {
     body: TextColor,
@@ -369,6 +496,21 @@ pub struct ColorConfig {
     tags: TextColor,
     title: TextColor,
 }
+impl ColorConfig {
+    pub fn body(&self) -> TextColor {
+        self.body
+    }
+    pub fn date(&self) -> TextColor {
+        self.date
+    }
+    pub fn tags(&self) -> TextColor {
+        self.tags
+    }
+    pub fn title(&self) -> TextColor {
+        self.title
+    }
+}
+
 impl Default for ColorConfig {
     fn default() -> Self {
         Self {
@@ -395,7 +537,6 @@ pub enum TextColor {
 }
 
 impl TextColor {
-    #[allow(dead_code)]
     pub fn get_termcolor(&self) -> Option<Color> {
         match self {
             Self::None => None,
@@ -411,7 +552,7 @@ impl TextColor {
     }
 }
 
-#[derive(Debug, Serialize, Deserialize, Copy, Clone, ValueEnum)]
+#[derive(Debug, Default, Serialize, Deserialize, Copy, Clone, ValueEnum)]
 #[serde(rename_all = "lowercase")]
 pub enum DisplayConfig {
     Boxed,
@@ -423,18 +564,13 @@ pub enum DisplayConfig {
     Short,
     Tags,
     #[serde(alias = "txt")]
+    #[default]
     Text,
     Xml,
     #[serde(alias = "yml")]
     Yaml,
 }
 
-impl Default for DisplayConfig {
-    fn default() -> Self {
-        Self::Text
-    }
-}
-
 #[cfg(test)]
 mod test_config {
     use super::*;
@@ -461,6 +597,29 @@ tagsymbols: '%#@'
 template: false
 timeformat: '%F %r'
 version: v4.1
+"#;
+
+    const PASSWORD_STORE_YAML: &str = r#"journals:
+  default: ~/journal.txt
+  work:
+    journal: ~/work.txt
+    password_store: secret-service
+password_store: file
+"#;
+
+    // a config written by hand, whose layout and comments must survive
+    const HAND_WRITTEN_YAML: &str = r#"# my jrnl config
+encrypt: false
+journals:
+  default: "~/journal.txt"  # the main one
+
+  work:
+    # kept apart
+    journal: ~/work.txt
+  secret:
+    encrypt: false # for now
+    journal: ~/secret.txt
+timeformat: '%F %r'
 "#;
 
     fn sample_settings() -> Settings {
@@ -515,6 +674,10 @@ version: v4.1
         assert!(settings.encrypt("other").unwrap());
         assert!(settings.highlight("default").unwrap());
         assert!(settings.highlight("other").unwrap());
+        assert_eq!(
+            settings.password_store("other").unwrap(),
+            PasswordStoreConfig::Prompt
+        );
     }
 
     #[test]
@@ -544,4 +707,49 @@ version: v4.1
             panic!("expected {expected_toplevel}, got {kind}");
         }
     }
+
+    #[test]
+    fn test_password_store() {
+        let settings: Settings = serde_yml::from_str(PASSWORD_STORE_YAML).unwrap();
+        assert_eq!(
+            settings.password_store("work").unwrap(),
+            PasswordStoreConfig::SecretService
+        );
+        assert_eq!(
+            settings.password_store("default").unwrap(),
+            PasswordStoreConfig::File
+        );
+    }
+
+    #[test]
+    fn test_with_journal_encrypt() {
+        let edit = |name, encrypt| with_journal_encrypt(HAND_WRITTEN_YAML, name, encrypt).unwrap();
+        assert_eq!(
+            edit("default", true),
+            HAND_WRITTEN_YAML.replace(
+                "  default: \"~/journal.txt\"  # the main one\n",
+                "  default:\n    encrypt: true\n    journal: \"~/journal.txt\"  # the main one\n"
+            )
+        );
+        assert_eq!(
+            edit("work", true),
+            HAND_WRITTEN_YAML.replace("  work:\n", "  work:\n    encrypt: true\n")
+        );
+        assert_eq!(
+            edit("secret", true),
+            HAND_WRITTEN_YAML.replace("encrypt: false # for now", "encrypt: true # for now")
+        );
+
+        let unterminated = "journals:\n  default: ~/journal.txt";
+        assert_eq!(
+            with_journal_encrypt(unterminated, "default", false).unwrap(),
+            "journals:\n  default:\n    encrypt: false\n    journal: ~/journal.txt\n"
+        );
+
+        let err = with_journal_encrypt(HAND_WRITTEN_YAML, "missing", true).unwrap_err();
+        assert_eq!(err.kind(), JrnlErrorKind::MissingJournalConfig);
+        let flow = "journals:\n  default: {journal: ~/journal.txt}\n";
+        let err = with_journal_encrypt(flow, "default", true).unwrap_err();
+        assert_eq!(err.kind(), JrnlErrorKind::InvalidConfig);
+    }
 }
@@ -0,0 +1,361 @@
+use chrono::NaiveDate;
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Path, PathBuf, MAIN_SEPARATOR};
+
+use crate::credentials::{self, CredentialStore};
+use crate::crypto;
+use crate::entry::Entry;
+use crate::error::{JrnlError, JrnlErrorKind, ParseError};
+use crate::journal::{self, Journal};
+use crate::settings::Settings;
+
+mod folder;
+#[cfg(feature = "sqlite")]
+mod sqlite;
+
+pub use folder::FolderStore;
+#[cfg(feature = "sqlite")]
+pub use sqlite::SqliteStore;
+
+type Result<T> = std::result::Result<T, Box<dyn std::error::Error>>;
+
+/// Where a journal's entries live. Commands go through this rather than
+/// touching journal files themselves.
+pub trait JournalStore {
+    /// The entries on the days for which `include` holds. Stores may return
+    /// others too, but can skip reading days that aren't included.
+    fn load(&mut self, include: &dyn Fn(NaiveDate) -> bool) -> Result<Journal>;
+
+    /// The entries on included days that contain `text`, ignoring case, if
+    /// the store can look them up faster than `load` and filtering. Others
+    /// return `None`.
+    fn load_containing(
+        &mut self,
+        _text: &str,
+        _include: &dyn Fn(NaiveDate) -> bool,
+    ) -> Result<Option<Journal>> {
+        Ok(None)
+    }
+
+    /// Everything that looks wrong in how the journal is written, without
+    /// changing anything. Stores that aren't text have nothing to check.
+    fn check(&mut self) -> Result<Vec<ParseError>> {
+        Ok(vec![])
+    }
+
+    /// Add an entry.
+    fn append(&mut self, entry: &Entry) -> Result<()>;
+
+    /// Write back a journal obtained from `load`, replacing what was loaded.
+    fn replace(&mut self, journal: &Journal) -> Result<()>;
+
+    /// Remove `entries`, returning how many were found.
+    fn delete(&mut self, entries: &[Entry]) -> Result<usize> {
+        let days = entries
+            .iter()
+            .map(|entry| entry.time.date())
+            .collect::<HashSet<_>>();
+        let mut journal = self.load(&|date| days.contains(&date))?;
+        let deleted = journal.remove(entries);
+        if deleted > 0 {
+            self.replace(&journal)?;
+        }
+        Ok(deleted)
+    }
+}
+
+/// The store for a journal: an SQLite database for a `sqlite://` path, a
+/// folder journal if its path is a directory, an encrypted one if configured
+/// or already encrypted, or else a plain text file.
+pub fn open(
+    settings: &Settings,
+    journal_name: &str,
+    journal_file: &Path,
+) -> Result<Box<dyn JournalStore>> {
+    open_with(
+        settings,
+        journal_name,
+        journal_file,
+        settings.encrypt(journal_name)?,
+    )
+}
+
+/// Open the journal at `journal_file` like `open`, but encrypted only if
+/// `encrypt` or if the file already is, whatever the settings say.
+pub fn open_with(
+    settings: &Settings,
+    journal_name: &str,
+    journal_file: &Path,
+    encrypt: bool,
+) -> Result<Box<dyn JournalStore>> {
+    let timeformat = settings.timeformat(journal_name)?;
+    if let Some(database) = sqlite_path(journal_file) {
+        if encrypt {
+            Err(no_encryption(journal_file, "an sqlite"))?
+        }
+        return open_sqlite(journal_name, database, &settings.tagsymbols(journal_name)?);
+    }
+    if journal_file.is_dir() {
+        if encrypt {
+            Err(no_encryption(journal_file, "a folder"))?
+        }
+        return Ok(Box::new(FolderStore::new(
+            journal_name,
+            journal_file,
+            &timeformat,
+        )));
+    }
+    if encrypt || crypto::is_encrypted(&fs::read(journal_file)?) {
+        return Ok(Box::new(EncryptedStore {
+            name: journal_name.to_owned(),
+            path: journal_file.to_owned(),
+            timeformat,
+            credentials: credentials::store(settings, journal_name)?,
+            password: None,
+        }));
+    }
+    Ok(Box::new(FlatStore {
+        name: journal_name.to_owned(),
+        path: journal_file.to_owned(),
+        timeformat,
+    }))
+}
+
+/// The error for trying to encrypt the journal at `journal_file`, which is of
+/// a `kind` that can't be, such as a folder journal.
+pub fn no_encryption(journal_file: &Path, kind: &str) -> JrnlError {
+    JrnlError::new(JrnlErrorKind::UnsupportedEncryption)
+        .context(format!("'{}' is {kind} journal", journal_file.display()))
+}
+
+/// Parse the journal `text` read from `source`, warning about everything
+/// that looks wrong in it. Text that isn't an entry isn't shown, but is kept
+/// as is when the journal is rewritten.
+fn read_journal(name: &str, text: &str, timeformat: &str, source: &Path) -> Journal {
+    for problem in check_text(name, text, timeformat, source) {
+        eprintln!("{}", problem.render("warning"));
+    }
+    Journal::read(name, text, timeformat)
+}
+
+/// Everything that looks wrong in the journal `text` read from `source`.
+fn check_text(name: &str, text: &str, timeformat: &str, source: &Path) -> Vec<ParseError> {
+    journal::check(name, text, timeformat)
+        .into_iter()
+        .map(|problem| problem.in_file(source))
+        .collect()
+}
+
+/// The database file of a journal configured as `sqlite://<path>`.
+pub fn sqlite_path(journal_file: &Path) -> Option<&Path> {
+    journal_file
+        .to_str()
+        .and_then(|path| path.strip_prefix("sqlite://"))
+        .map(Path::new)
+}
+
+#[cfg(feature = "sqlite")]
+fn open_sqlite(
+    journal_name: &str,
+    database: &Path,
+    tagsymbols: &str,
+) -> Result<Box<dyn JournalStore>> {
+    Ok(Box::new(SqliteStore::open(
+        journal_name,
+        database,
+        tagsymbols,
+    )?))
+}
+
+#[cfg(not(feature = "sqlite"))]
+fn open_sqlite(
+    _journal_name: &str,
+    database: &Path,
+    _tagsymbols: &str,
+) -> Result<Box<dyn JournalStore>> {
+    let context = format!(
+        "can't open '{}', rebuild jrnl with --features sqlite",
+        database.display()
+    );
+    Err(JrnlError::new(JrnlErrorKind::NoSqliteSupport)
+        .context(context)
+        .into())
+}
+
+/// Create an empty journal at `journal_file` if there's nothing there yet. A
+/// trailing slash asks for a folder journal, and databases are created when
+/// opened.
+pub fn create(journal_file: &Path) -> Result<()> {
+    let database = sqlite_path(journal_file);
+    let path = database.unwrap_or(journal_file);
+    if fs::exists(path)? {
+        return Ok(());
+    }
+    if path.to_string_lossy().ends_with(['/', MAIN_SEPARATOR]) {
+        fs::create_dir_all(path)?;
+        return Ok(());
+    }
+    if let Some(parent) = path
+        .parent()
+        .filter(|parent| !parent.as_os_str().is_empty())
+    {
+        fs::create_dir_all(parent)?;
+    }
+    if database.is_none() {
+        fs::File::create(path)?;
+    }
+    Ok(())
+}
+
+/// A journal in a single plain text file.
+pub struct FlatStore {
+    name: String,
+    path: PathBuf,
+    timeformat: String,
+}
+
+impl JournalStore for FlatStore {
+    fn load(&mut self, _include: &dyn Fn(NaiveDate) -> bool) -> Result<Journal> {
+        let text = journal::read_text(&self.path, None)?;
+        Ok(read_journal(
+            &self.name,
+            &text,
+            &self.timeformat,
+            &self.path,
+        ))
+    }
+
+    fn check(&mut self) -> Result<Vec<ParseError>> {
+        let text = journal::read_text(&self.path, None)?;
+        Ok(check_text(&self.name, &text, &self.timeformat, &self.path))
+    }
+
+    /// Existing contents are left untouched.
+    fn append(&mut self, entry: &Entry) -> Result<()> {
+        Ok(Journal::append_to_file(
+            &self.path,
+            entry,
+            &self.timeformat,
+        )?)
+    }
+
+    fn replace(&mut self, journal: &Journal) -> Result<()> {
+        journal::write_text(&self.path, &journal.format(&self.timeformat), None)
+    }
+}
+
+/// A journal in a single encrypted file, which is rewritten as a whole. The
+/// password is asked for at most once.
+pub struct EncryptedStore {
+    name: String,
+    path: PathBuf,
+    timeformat: String,
+    credentials: Option<Box<dyn CredentialStore>>,
+    password: Option<String>,
+}
+
+impl EncryptedStore {
+    fn text(&mut self) -> Result<String> {
+        if let Some(password) = &self.password {
+            return journal::read_text(&self.path, Some(password));
+        }
+        let (text, password) =
+            journal::unlock(&self.path, &self.name, self.credentials.as_deref(), true)?;
+        self.password = password;
+        Ok(text)
+    }
+
+    /// The password to encrypt with, a new one if the journal isn't encrypted
+    /// yet.
+    fn password(&mut self) -> Result<String> {
+        if self.password.is_none() && crypto::may_be_encrypted(&fs::read(&self.path)?) {
+            self.text()?;
+        }
+        if self.password.is_none() {
+            let password = credentials::new_password(&self.name, self.credentials.as_deref())?;
+            self.password = Some(password);
+        }
+        Ok(self.password.clone().unwrap_or_default())
+    }
+}
+
+impl JournalStore for EncryptedStore {
+    fn load(&mut self, _include: &dyn Fn(NaiveDate) -> bool) -> Result<Journal> {
+        let text = self.text()?;
+        Ok(read_journal(
+            &self.name,
+            &text,
+            &self.timeformat,
+            &self.path,
+        ))
+    }
+
+    fn check(&mut self) -> Result<Vec<ParseError>> {
+        let text = self.text()?;
+        Ok(check_text(&self.name, &text, &self.timeformat, &self.path))
+    }
+
+    fn append(&mut self, entry: &Entry) -> Result<()> {
+        let mut text = self.text()?;
+        Journal::append_to_text(&mut text, entry, &self.timeformat);
+        let password = self.password()?;
+        journal::write_text(&self.path, &text, Some(&password))
+    }
+
+    fn replace(&mut self, journal: &Journal) -> Result<()> {
+        let password = self.password()?;
+        journal::write_text(
+            &self.path,
+            &journal.format(&self.timeformat),
+            Some(&password),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::NaiveDateTime;
+
+    #[test]
+    fn test_flat_store() {
+        let file = tempfile::NamedTempFile::new().unwrap();
+        let mut store = FlatStore {
+            name: "default".to_owned(),
+            path: file.path().to_owned(),
+            timeformat: "%F %r".to_owned(),
+        };
+        let time = |s| NaiveDateTime::parse_from_str(s, "%F %R").unwrap();
+        let late = Entry::compose(time("2023-01-13 08:00"), "Late.");
+        let early = Entry::compose(time("2023-01-12 08:00"), "Early. Body");
+        store.append(&late).unwrap();
+        store.append(&early).unwrap();
+        assert_eq!(
+            fs::read_to_string(file.path()).unwrap(),
+            "[2023-01-13 08:00:00 AM] Late.\n\n[2023-01-12 08:00:00 AM] Early.\nBody\n"
+        );
+
+        // order is kept as is
+        let before = fs::read_to_string(file.path()).unwrap();
+        let journal = store.load(&|_| true).unwrap();
+        store.replace(&journal).unwrap();
+        assert_eq!(fs::read_to_string(file.path()).unwrap(), before);
+
+        assert_eq!(store.delete(&[late.clone(), late]).unwrap(), 1);
+        assert_eq!(
+            store.load(&|_| true).unwrap().entries(),
+            std::slice::from_ref(&early)
+        );
+
+        // of identical entries, only as many as selected are deleted
+        for _ in 0..2 {
+            store.append(&early).unwrap();
+        }
+        assert_eq!(store.delete(std::slice::from_ref(&early)).unwrap(), 1);
+        assert_eq!(
+            store.load(&|_| true).unwrap().entries(),
+            [early.clone(), early]
+        );
+    }
+}
@@ -0,0 +1,296 @@
+use chrono::NaiveDate;
+use indexmap::IndexMap;
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use super::{JournalStore, Result};
+use crate::entry::Entry;
+use crate::error::ParseError;
+use crate::journal::{self, Journal};
+
+/// A journal kept as one plain text file per day, `YYYY/MM/DD.txt`, under a
+/// directory, so that syncing and merging only ever deals with small files.
+pub struct FolderStore {
+    name: String,
+    dir: PathBuf,
+    timeformat: String,
+    /// Days read by the last `load` that had a file.
+    loaded: HashSet<NaiveDate>,
+    /// The files of loaded days as read, so that the text before their first
+    /// entry and their unchanged entries are kept on rewrite.
+    days: HashMap<NaiveDate, Journal>,
+}
+
+impl FolderStore {
+    pub fn new(name: &str, dir: &Path, timeformat: &str) -> Self {
+        Self {
+            name: name.to_owned(),
+            dir: dir.to_owned(),
+            timeformat: timeformat.to_owned(),
+            loaded: HashSet::new(),
+            days: HashMap::new(),
+        }
+    }
+
+    fn day_file(&self, date: NaiveDate) -> PathBuf {
+        self.dir
+            .join(date.format("%Y").to_string())
+            .join(date.format("%m").to_string())
+            .join(date.format("%d.txt").to_string())
+    }
+}
+
+impl JournalStore for FolderStore {
+    /// Only the files of included days are opened.
+    fn load(&mut self, include: &dyn Fn(NaiveDate) -> bool) -> Result<Journal> {
+        self.loaded.clear();
+        self.days.clear();
+        let mut entries = vec![];
+        for (date, path) in day_files(&self.dir)? {
+            if !include(date) {
+                continue;
+            }
+            let text = fs::read_to_string(&path)?;
+            let day = super::read_journal(&self.name, &text, &self.timeformat, &path);
+            entries.extend(day.entries().iter().cloned());
+            self.days.insert(date, day);
+            self.loaded.insert(date);
+        }
+        // a stable sort, so identical entries are all kept, in file order
+        entries.sort_by_key(|entry| entry.time);
+        Ok(Journal::from_entries(&self.name, entries))
+    }
+
+    fn check(&mut self) -> Result<Vec<ParseError>> {
+        let mut problems = vec![];
+        for (_, path) in day_files(&self.dir)? {
+            let text = fs::read_to_string(&path)?;
+            problems.extend(super::check_text(
+                &self.name,
+                &text,
+                &self.timeformat,
+                &path,
+            ));
+        }
+        Ok(problems)
+    }
+
+    /// Only the file of the entry's day is touched.
+    fn append(&mut self, entry: &Entry) -> Result<()> {
+        let path = self.day_file(entry.time.date());
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        Journal::append_to_file(&path, entry, &self.timeformat)?;
+        Ok(())
+    }
+
+    /// Only the files of days whose contents change are touched, and those of
+    /// loaded days left without entries are removed.
+    fn replace(&mut self, journal: &Journal) -> Result<()> {
+        let mut days: IndexMap<NaiveDate, Vec<Entry>> = self
+            .days
+            .iter()
+            .filter(|(_, day)| !day.preamble().is_empty())
+            .map(|(&date, _)| (date, vec![]))
+            .collect();
+        for entry in journal.entries() {
+            days.entry(entry.time.date())
+                .or_default()
+                .push(entry.clone());
+        }
+        for (&date, entries) in &days {
+            let path = self.day_file(date);
+            let text = self
+                .days
+                .get(&date)
+                .cloned()
+                .unwrap_or_else(|| Journal::from_entries(&self.name, vec![]))
+                .with_entries(entries.clone())
+                .format(&self.timeformat);
+            if fs::read_to_string(&path).is_ok_and(|existing| existing == text) {
+                continue;
+            }
+            if let Some(parent) = path.parent() {
+                fs::create_dir_all(parent)?;
+            }
+            journal::write_atomic(&path, text.as_bytes())?;
+        }
+        for date in self.loaded.iter().filter(|date| !days.contains_key(*date)) {
+            fs::remove_file(self.day_file(*date))?;
+        }
+        self.loaded = days.into_keys().collect();
+        Ok(())
+    }
+}
+
+/// All day files under `dir` in chronological order, skipping anything not
+/// laid out as `YYYY/MM/DD.txt`.
+fn day_files(dir: &Path) -> Result<Vec<(NaiveDate, PathBuf)>> {
+    let mut files = vec![];
+    for year in subdirs(dir)? {
+        for month in subdirs(&year)? {
+            for dir_entry in fs::read_dir(&month)? {
+                let path = dir_entry?.path();
+                let date = (|| {
+                    let name = |p: &Path| p.file_name()?.to_str().map(str::to_owned);
+                    let day = path.file_stem()?.to_str()?;
+                    let date = format!("{}-{}-{day}", name(&year)?, name(&month)?);
+                    NaiveDate::parse_from_str(&date, "%Y-%m-%d").ok()
+                })();
+                match date {
+                    Some(date) if path.extension() == Some("txt".as_ref()) => {
+                        files.push((date, path))
+                    }
+                    _ => (),
+                }
+            }
+        }
+    }
+    files.sort();
+    Ok(files)
+}
+
+fn subdirs(dir: &Path) -> Result<Vec<PathBuf>> {
+    let mut dirs = vec![];
+    for dir_entry in fs::read_dir(dir)? {
+        let path = dir_entry?.path();
+        let numeric = path
+            .file_name()
+            .and_then(|name| name.to_str())
+            .is_some_and(|name| name.chars().all(|c| c.is_ascii_digit()));
+        if path.is_dir() && numeric {
+            dirs.push(path);
+        }
+    }
+    Ok(dirs)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::NaiveDateTime;
+
+    fn time(s: &str) -> NaiveDateTime {
+        NaiveDateTime::parse_from_str(s, "%F %R").unwrap()
+    }
+
+    fn all(_: NaiveDate) -> bool {
+        true
+    }
+
+    #[test]
+    fn test_append_and_load() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut store = FolderStore::new("default", dir.path(), "%F %r");
+        let first = Entry::compose(time("2023-01-12 08:00"), "First. Body");
+        let second = Entry::compose(time("2023-01-12 17:00"), "Second.");
+        let other = Entry::compose(time("2022-12-31 23:59"), "Last year.");
+        for entry in [&first, &second, &other] {
+            store.append(entry).unwrap();
+        }
+        assert_eq!(
+            fs::read_to_string(dir.path().join("2023/01/12.txt")).unwrap(),
+            "[2023-01-12 08:00:00 AM] First.\nBody\n\n[2023-01-12 05:00:00 PM] Second.\n"
+        );
+        assert!(dir.path().join("2022/12/31.txt").exists());
+        // ignored: not a day file
+        fs::write(dir.path().join("2023/01/notes.md"), "hi").unwrap();
+
+        let journal = store.load(&all).unwrap();
+        let titles = journal
+            .entries()
+            .iter()
+            .map(|e| e.title())
+            .collect::<Vec<_>>();
+        assert_eq!(titles, ["Last year.", "First.", "Second."]);
+    }
+
+    #[test]
+    fn test_identical_entries_survive_rewrite() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut store = FolderStore::new("default", dir.path(), "%F %r");
+        let entry = Entry::compose(time("2023-01-12 08:00"), "Twice.");
+        store.append(&entry).unwrap();
+        store.append(&entry).unwrap();
+
+        let journal = store.load(&all).unwrap();
+        assert_eq!(journal.entries().len(), 2);
+        store.replace(&journal).unwrap();
+        assert_eq!(store.load(&all).unwrap().entries().len(), 2);
+    }
+
+    #[test]
+    fn test_load_only_opens_matching_days() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut store = FolderStore::new("default", dir.path(), "%F %r");
+        let entry = Entry::compose(time("2023-01-12 08:00"), "Kept.");
+        store.append(&entry).unwrap();
+        fs::create_dir_all(dir.path().join("2023/02")).unwrap();
+        fs::write(dir.path().join("2023/02/01.txt"), "not a journal").unwrap();
+
+        let in_january = |date: NaiveDate| date.format("%m").to_string() == "01";
+        let journal = store.load(&in_january).unwrap();
+        assert_eq!(journal.entries(), std::slice::from_ref(&entry));
+
+        // text that isn't an entry is kept when the day is rewritten
+        let mut journal = store.load(&all).unwrap();
+        assert_eq!(journal.entries(), [entry]);
+        journal.merge(vec![Entry::compose(time("2023-02-01 08:00"), "New.")]);
+        store.replace(&journal).unwrap();
+        assert_eq!(
+            fs::read_to_string(dir.path().join("2023/02/01.txt")).unwrap(),
+            "not a journal\n\n[2023-02-01 08:00:00 AM] New.\n"
+        );
+    }
+
+    #[test]
+    fn test_replace_touches_changed_days_only() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut store = FolderStore::new("default", dir.path(), "%F %r");
+        for (when, text) in [
+            ("2023-01-12 08:00", "One."),
+            ("2023-01-13 08:00", "Two."),
+            ("2023-01-14 08:00", "Gone."),
+        ] {
+            store.append(&Entry::compose(time(when), text)).unwrap();
+        }
+        let untouched = dir.path().join("2023/01/12.txt");
+        let modified = || fs::metadata(&untouched).unwrap().modified().unwrap();
+        let before = modified();
+
+        let mut journal = store.load(&all).unwrap();
+        journal.merge(vec![Entry::compose(time("2023-01-13 09:00"), "Three.")]);
+        journal.remove(&[Entry::compose(time("2023-01-14 08:00"), "Gone.")]);
+        store.replace(&journal).unwrap();
+
+        assert_eq!(modified(), before);
+        assert_eq!(
+            fs::read_to_string(dir.path().join("2023/01/13.txt")).unwrap(),
+            "[2023-01-13 08:00:00 AM] Two.\n\n[2023-01-13 09:00:00 AM] Three.\n"
+        );
+        assert!(!dir.path().join("2023/01/14.txt").exists());
+    }
+
+    #[test]
+    fn test_unchanged_entries_are_kept_as_written() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut store = FolderStore::new("default", dir.path(), "%F %r");
+        fs::create_dir_all(dir.path().join("2023/01")).unwrap();
+        let day = dir.path().join("2023/01/12.txt");
+        fs::write(
+            &day,
+            "[2023-01-12 08:00] Kept.\n  indented\n\n[2023-01-12 09:00] Gone.\n",
+        )
+        .unwrap();
+
+        let mut journal = store.load(&all).unwrap();
+        journal.remove(&[Entry::compose(time("2023-01-12 09:00"), "Gone.")]);
+        store.replace(&journal).unwrap();
+        assert_eq!(
+            fs::read_to_string(&day).unwrap(),
+            "[2023-01-12 08:00] Kept.\n  indented\n"
+        );
+    }
+}
@@ -0,0 +1,363 @@
+use chrono::{NaiveDate, NaiveDateTime};
+use rusqlite::{params, Connection, OptionalExtension};
+use std::path::Path;
+
+use super::{JournalStore, Result};
+use crate::entry::Entry;
+use crate::journal::Journal;
+
+/// How entry times are stored, so that they sort as text.
+const TIME_FORMAT: &str = "%F %T";
+
+/// Schema changes, applied in order. `PRAGMA user_version` records how many
+/// have been applied to a database.
+const MIGRATIONS: &[&str] = &["
+    CREATE TABLE entries (
+        id INTEGER PRIMARY KEY,
+        time TEXT NOT NULL,
+        title TEXT NOT NULL,
+        body TEXT NOT NULL
+    );
+    CREATE INDEX entries_time ON entries (time);
+
+    CREATE TABLE tags (
+        entry_id INTEGER NOT NULL REFERENCES entries (id) ON DELETE CASCADE,
+        tag TEXT NOT NULL COLLATE NOCASE
+    );
+    CREATE INDEX tags_tag ON tags (tag);
+    CREATE INDEX tags_entry_id ON tags (entry_id);
+
+    -- trigram tokens allow the same substring matching as --contains
+    CREATE VIRTUAL TABLE entries_fts USING fts5 (
+        title, body, content = 'entries', content_rowid = 'id', tokenize = 'trigram'
+    );
+    CREATE TRIGGER entries_ai AFTER INSERT ON entries BEGIN
+        INSERT INTO entries_fts (rowid, title, body) VALUES (new.id, new.title, new.body);
+    END;
+    CREATE TRIGGER entries_ad AFTER DELETE ON entries BEGIN
+        INSERT INTO entries_fts (entries_fts, rowid, title, body)
+            VALUES ('delete', old.id, old.title, old.body);
+    END;
+"];
+
+/// A journal in an SQLite database, selected by a `sqlite://` journal path.
+/// Tags are kept in their own table and titles and bodies are indexed for full
+/// text search.
+pub struct SqliteStore {
+    name: String,
+    conn: Connection,
+    tagsymbols: String,
+    /// Rows read by the last load.
+    loaded: Vec<i64>,
+}
+
+impl SqliteStore {
+    pub fn open(name: &str, path: &Path, tagsymbols: &str) -> Result<Self> {
+        let mut conn = Connection::open(path)?;
+        conn.pragma_update(None, "foreign_keys", true)?;
+        migrate(&mut conn)?;
+        Ok(Self {
+            name: name.to_owned(),
+            conn,
+            tagsymbols: tagsymbols.to_owned(),
+            loaded: vec![],
+        })
+    }
+
+    fn query(
+        &mut self,
+        sql: &str,
+        params: impl rusqlite::Params,
+        include: &dyn Fn(NaiveDate) -> bool,
+    ) -> Result<Journal> {
+        let mut statement = self.conn.prepare(sql)?;
+        let mut rows = statement.query(params)?;
+        let mut entries = vec![];
+        self.loaded.clear();
+        while let Some(row) = rows.next()? {
+            let time: String = row.get("time")?;
+            let time = NaiveDateTime::parse_from_str(&time, TIME_FORMAT)?;
+            if !include(time.date()) {
+                continue;
+            }
+            let title: String = row.get("title")?;
+            let body: String = row.get("body")?;
+            entries.push(Entry::new(time, &title, &body));
+            self.loaded.push(row.get("id")?);
+        }
+        Ok(Journal::from_entries(&self.name, entries))
+    }
+
+    /// The stored times from the first day with entries for which `include`
+    /// holds up to the day after the last, if there is one, so that queries
+    /// read only the rows in between. Only the index on times is read.
+    fn included_range(
+        &self,
+        include: &dyn Fn(NaiveDate) -> bool,
+    ) -> Result<Option<(String, String)>> {
+        let mut statement = self
+            .conn
+            .prepare("SELECT DISTINCT substr(time, 1, 10) AS day FROM entries ORDER BY day")?;
+        let mut rows = statement.query([])?;
+        let (mut first, mut last) = (None, None);
+        while let Some(row) = rows.next()? {
+            let day = NaiveDate::parse_from_str(&row.get::<_, String>("day")?, "%F")?;
+            if include(day) {
+                first.get_or_insert(day);
+                last = Some(day);
+            }
+        }
+        Ok(first.zip(last).map(|(first, last)| {
+            let after = last.succ_opt().unwrap_or(NaiveDate::MAX);
+            (
+                first.format("%F").to_string(),
+                after.format("%F").to_string(),
+            )
+        }))
+    }
+}
+
+fn migrate(conn: &mut Connection) -> Result<()> {
+    let applied: usize = conn.pragma_query_value(None, "user_version", |row| row.get(0))?;
+    for (version, migration) in MIGRATIONS.iter().enumerate().skip(applied) {
+        let tx = conn.transaction()?;
+        tx.execute_batch(migration)?;
+        tx.pragma_update(None, "user_version", version + 1)?;
+        tx.commit()?;
+    }
+    Ok(())
+}
+
+fn insert(tx: &rusqlite::Transaction, entry: &Entry, tagsymbols: &str) -> Result<()> {
+    tx.execute(
+        "INSERT INTO entries (time, title, body) VALUES (?1, ?2, ?3)",
+        params![
+            entry.time.format(TIME_FORMAT).to_string(),
+            entry.title(),
+            entry.text()
+        ],
+    )?;
+    let id = tx.last_insert_rowid();
+    let mut insert_tag = tx.prepare_cached("INSERT INTO tags (entry_id, tag) VALUES (?1, ?2)")?;
+    for tag in entry.tags(tagsymbols) {
+        insert_tag.execute(params![id, tag])?;
+    }
+    Ok(())
+}
+
+impl JournalStore for SqliteStore {
+    /// Only the rows from the first to the last included day are read.
+    fn load(&mut self, include: &dyn Fn(NaiveDate) -> bool) -> Result<Journal> {
+        let Some((from, to)) = self.included_range(include)? else {
+            self.loaded.clear();
+            return Ok(Journal::from_entries(&self.name, vec![]));
+        };
+        self.query(
+            "SELECT id, time, title, body FROM entries
+             WHERE time >= ?1 AND time < ?2
+             ORDER BY time, id",
+            [from, to],
+            include,
+        )
+    }
+
+    /// Looked up in the full text index for three or more characters.
+    fn load_containing(
+        &mut self,
+        text: &str,
+        include: &dyn Fn(NaiveDate) -> bool,
+    ) -> Result<Option<Journal>> {
+        if text.chars().count() < 3 {
+            return Ok(None);
+        }
+        let Some((from, to)) = self.included_range(include)? else {
+            self.loaded.clear();
+            return Ok(Some(Journal::from_entries(&self.name, vec![])));
+        };
+        let query = format!("\"{}\"", text.replace('"', "\"\""));
+        let journal = self.query(
+            "SELECT id, time, title, body FROM entries
+             WHERE id IN (SELECT rowid FROM entries_fts WHERE entries_fts MATCH ?1)
+               AND time >= ?2 AND time < ?3
+             ORDER BY time, id",
+            [query, from, to],
+            include,
+        )?;
+        Ok(Some(journal))
+    }
+
+    fn append(&mut self, entry: &Entry) -> Result<()> {
+        let tx = self.conn.transaction()?;
+        insert(&tx, entry, &self.tagsymbols)?;
+        tx.commit()?;
+        Ok(())
+    }
+
+    fn replace(&mut self, journal: &Journal) -> Result<()> {
+        let tx = self.conn.transaction()?;
+        {
+            let mut delete = tx.prepare_cached("DELETE FROM entries WHERE id = ?1")?;
+            for id in &self.loaded {
+                delete.execute([id])?;
+            }
+        }
+        for entry in journal.entries() {
+            insert(&tx, entry, &self.tagsymbols)?;
+        }
+        tx.commit()?;
+        self.loaded.clear();
+        Ok(())
+    }
+
+    /// Rows are deleted directly, without loading the journal.
+    fn delete(&mut self, entries: &[Entry]) -> Result<usize> {
+        let tx = self.conn.transaction()?;
+        let mut deleted = 0;
+        for entry in entries {
+            let id = tx
+                .query_row(
+                    "SELECT id FROM entries WHERE time = ?1 AND title = ?2 AND body = ?3 LIMIT 1",
+                    params![
+                        entry.time.format(TIME_FORMAT).to_string(),
+                        entry.title(),
+                        entry.text()
+                    ],
+                    |row| row.get::<_, i64>(0),
+                )
+                .optional()?;
+            if let Some(id) = id {
+                deleted += tx.execute("DELETE FROM entries WHERE id = ?1", [id])?;
+            }
+        }
+        tx.commit()?;
+        Ok(deleted)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Datelike;
+
+    fn time(s: &str) -> NaiveDateTime {
+        NaiveDateTime::parse_from_str(s, "%F %R").unwrap()
+    }
+
+    fn all(_: NaiveDate) -> bool {
+        true
+    }
+
+    fn store() -> (tempfile::TempDir, SqliteStore) {
+        let dir = tempfile::tempdir().unwrap();
+        let store = SqliteStore::open("default", &dir.path().join("journal.db"), "#@").unwrap();
+        (dir, store)
+    }
+
+    #[test]
+    fn test_append_load_delete() {
+        let (_dir, mut store) = store();
+        let late = Entry::compose(time("2023-01-13 08:00"), "Late. In the @garden");
+        let early = Entry::compose(time("2023-01-12 08:00"), "Early. With @Alice");
+        store.append(&late).unwrap();
+        store.append(&early).unwrap();
+
+        let journal = store.load(&all).unwrap();
+        assert_eq!(journal.entries(), [early.clone(), late.clone()]);
+
+        let tags: Vec<String> = store
+            .conn
+            .prepare("SELECT tag FROM tags WHERE tag = '@alice'")
+            .unwrap()
+            .query_map([], |row| row.get(0))
+            .unwrap()
+            .collect::<rusqlite::Result<_>>()
+            .unwrap();
+        assert_eq!(tags, ["@Alice"]);
+
+        assert_eq!(store.delete(&[late.clone(), late]).unwrap(), 1);
+        assert_eq!(store.load(&all).unwrap().entries(), [early]);
+        let tag_count: i64 = store
+            .conn
+            .query_row("SELECT count(*) FROM tags", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(tag_count, 1);
+    }
+
+    #[test]
+    fn test_load_included_days() {
+        let (_dir, mut store) = store();
+        for when in ["2023-01-11 08:00", "2023-01-12 08:00", "2023-01-13 08:00"] {
+            store.append(&Entry::compose(time(when), "Day.")).unwrap();
+        }
+        let days = |journal: Journal| {
+            journal
+                .entries()
+                .iter()
+                .map(|e| e.time.format("%d").to_string())
+                .collect::<Vec<_>>()
+        };
+        let not_12th = |date: NaiveDate| date.day() != 12;
+        assert_eq!(days(store.load(&not_12th).unwrap()), ["11", "13"]);
+        assert_eq!(
+            store.included_range(&not_12th).unwrap(),
+            Some(("2023-01-11".to_owned(), "2023-01-14".to_owned()))
+        );
+        let in_2022 = |date: NaiveDate| date.year() == 2022;
+        assert!(store.load(&in_2022).unwrap().entries().is_empty());
+
+        // rows outside the range are left alone on replace
+        let from_13th = |date: NaiveDate| date.day() >= 13;
+        let journal = store.load(&from_13th).unwrap();
+        assert_eq!(
+            store.included_range(&from_13th).unwrap().unwrap().0,
+            "2023-01-13"
+        );
+        store.replace(&journal).unwrap();
+        assert_eq!(days(store.load(&all).unwrap()), ["11", "12", "13"]);
+    }
+
+    #[test]
+    fn test_load_containing() {
+        let (_dir, mut store) = store();
+        store
+            .append(&Entry::compose(
+                time("2023-01-12 08:00"),
+                "Garden. Planted tomatoes",
+            ))
+            .unwrap();
+        store
+            .append(&Entry::compose(time("2023-01-13 08:00"), "Work. Meetings"))
+            .unwrap();
+
+        let titles = |journal: Journal| {
+            journal
+                .entries()
+                .iter()
+                .map(|e| e.title().to_owned())
+                .collect::<Vec<_>>()
+        };
+        let found = store.load_containing("TOMATO", &all).unwrap().unwrap();
+        assert_eq!(titles(found), ["Garden."]);
+        let found = store.load_containing("eet", &all).unwrap().unwrap();
+        assert_eq!(titles(found), ["Work."]);
+        assert!(store.load_containing("ee", &all).unwrap().is_none());
+    }
+
+    #[test]
+    fn test_replace_and_reopen() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("journal.db");
+        let mut store = SqliteStore::open("default", &path, "#@").unwrap();
+        store
+            .append(&Entry::compose(time("2023-01-12 08:00"), "Old."))
+            .unwrap();
+        let mut journal = store.load(&all).unwrap();
+        journal.merge(vec![Entry::compose(time("2023-01-11 08:00"), "Older.")]);
+        store.replace(&journal).unwrap();
+        drop(store);
+
+        // migrations aren't applied twice
+        let mut store = SqliteStore::open("default", &path, "#@").unwrap();
+        assert_eq!(store.load(&all).unwrap().entries(), journal.entries());
+    }
+}
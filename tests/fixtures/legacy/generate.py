@@ -0,0 +1,65 @@
+"""Generate journals encrypted the way Python jrnl did.
+
+v1 is jrnl 1.x: AES-256-CBC keyed with SHA-256 of the password, the IV
+prepended, padded with PKCS#7 or, by some versions, with line breaks. v2 is jrnl 2.x and later: Fernet keyed with PBKDF2-HMAC-SHA256 of
+the password over a fixed salt. Requires the `cryptography` package.
+"""
+
+import base64
+import hashlib
+import os
+
+from cryptography.fernet import Fernet
+from cryptography.hazmat.primitives import hashes, padding
+from cryptography.hazmat.primitives.ciphers import Cipher, algorithms, modes
+from cryptography.hazmat.primitives.kdf.pbkdf2 import PBKDF2HMAC
+
+PASSWORD = "bad doggie no biscuit"
+TEXT = """[2013-06-09 03:45:00 PM] Life is good.
+But I'm better.
+
+[2013-06-10 03:40:00 PM] Everything is alright @work
+Entries have tags.
+"""
+HERE = os.path.dirname(os.path.abspath(__file__))
+
+
+def v1(text, password):
+    key = hashlib.sha256(password.encode("utf-8")).digest()
+    iv = os.urandom(16)
+    padder = padding.PKCS7(128).padder()
+    padded = padder.update(text.encode("utf-8")) + padder.finalize()
+    encryptor = Cipher(algorithms.AES(key), modes.CBC(iv)).encryptor()
+    return iv + encryptor.update(padded) + encryptor.finalize()
+
+
+def v1_newlines(text, password):
+    key = hashlib.sha256(password.encode("utf-8")).digest()
+    iv = os.urandom(16)
+    plain = text.encode("utf-8")
+    padded = plain + b"\n" * (16 - len(plain) % 16)
+    encryptor = Cipher(algorithms.AES(key), modes.CBC(iv)).encryptor()
+    return iv + encryptor.update(padded) + encryptor.finalize()
+
+
+def v2(text, password):
+    kdf = PBKDF2HMAC(
+        algorithm=hashes.SHA256(),
+        length=32,
+        salt=b"\xf2\xd5q\x0e\xc1\x8d.\xde\xdc\x8e6t\x89\x04\xce\xf8",
+        iterations=100_000,
+    )
+    key = base64.urlsafe_b64encode(kdf.derive(password.encode("utf-8")))
+    return Fernet(key).encrypt(text.encode("utf-8"))
+
+
+if __name__ == "__main__":
+    for name, encrypt in (
+        ("v1.journal", v1),
+        ("v1-newlines.journal", v1_newlines),
+        ("v2.journal", v2),
+    ):
+        with open(os.path.join(HERE, name), "wb") as f:
+            f.write(encrypt(TEXT, PASSWORD))
+    with open(os.path.join(HERE, "plain.txt"), "w") as f:
+        f.write(TEXT)